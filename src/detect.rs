@@ -0,0 +1,271 @@
+//! Detection of file types from file contents.
+
+use crate::{
+    extractors::objects::TEXTURE_TAGS,
+    header::{is_container, ScHeader},
+    utils::{decompress, LZHAM_MAGIC, ZSTD_MAGIC},
+};
+use byteorder::{ByteOrder, LittleEndian};
+use std::fmt;
+
+/// Magic bytes at the start of `KTX` (version 1) images.
+pub(crate) const KTX_MAGIC: [u8; 12] = [
+    0xAB, 0x4B, 0x54, 0x58, 0x20, 0x31, 0x31, 0xBB, 0x0D, 0x0A, 0x1A, 0x0A,
+];
+
+/// Magic bytes at the start of `KTX2` images.
+pub(crate) const KTX2_MAGIC: [u8; 12] = [
+    0xAB, 0x4B, 0x54, 0x58, 0x20, 0x32, 0x30, 0xBB, 0x0D, 0x0A, 0x1A, 0x0A,
+];
+
+/// Magic bytes at the start of `PVR` (version 3) images.
+pub(crate) const PVR_MAGIC: [u8; 4] = *b"PVR\x03";
+
+/// Magic bytes of legacy `PVR` (version 2) images. They are stored at offset 44.
+const PVR_LEGACY_MAGIC: [u8; 4] = *b"PVR!";
+
+/// Compression methods used by Supercell games.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Compression {
+    /// `LZMA`, with 4 bytes of the header removed.
+    Lzma,
+    /// `Zstandard`.
+    Zstd,
+    /// `LZHAM`, starting with `SCLZ`.
+    Lzham,
+}
+
+impl Compression {
+    /// Detects the compression method of compressed data.
+    ///
+    /// `None` is returned if the data doesn't look compressed.
+    ///
+    /// ## Arguments
+    ///
+    /// * `data`: Compressed data, without any `SC` header.
+    pub fn detect(data: &[u8]) -> Option<Self> {
+        if data.starts_with(&ZSTD_MAGIC) {
+            Some(Compression::Zstd)
+        } else if data.starts_with(&LZHAM_MAGIC) {
+            Some(Compression::Lzham)
+        } else if data.starts_with(&[0x5D, 0x00]) && data.len() >= 9 {
+            Some(Compression::Lzma)
+        } else {
+            None
+        }
+    }
+}
+
+impl fmt::Display for Compression {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Compression::Lzma => write!(f, "LZMA"),
+            Compression::Zstd => write!(f, "Zstandard"),
+            Compression::Lzham => write!(f, "LZHAM"),
+        }
+    }
+}
+
+/// Type of a file, as detected from its contents by [`detect`].
+///
+/// [`detect`]: fn.detect.html
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FileKind {
+    /// `.sc` file containing textures, usually named `_tex.sc`.
+    ScTexture,
+    /// `.sc` file containing shapes, movie clips and other objects.
+    ScObject,
+    /// `.sc` file whose contents couldn't be decompressed.
+    Sc,
+    /// Compressed `.csv` file, with or without an `SC` header.
+    CompressedCsv,
+    /// Plain, already decompressed `.csv` file.
+    Csv,
+    /// Compressed data which is not csv.
+    Compressed(Compression),
+    /// `KTX` or `KTX2` image.
+    Ktx,
+    /// `PVR` image.
+    Pvr,
+    /// Unknown type of file.
+    Unknown,
+}
+
+impl fmt::Display for FileKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FileKind::ScTexture => write!(f, "SC texture"),
+            FileKind::ScObject => write!(f, "SC object"),
+            FileKind::Sc => write!(f, "SC"),
+            FileKind::CompressedCsv => write!(f, "compressed csv"),
+            FileKind::Csv => write!(f, "plain csv"),
+            FileKind::Compressed(c) => write!(f, "{} compressed", c),
+            FileKind::Ktx => write!(f, "KTX image"),
+            FileKind::Pvr => write!(f, "PVR image"),
+            FileKind::Unknown => write!(f, "unknown"),
+        }
+    }
+}
+
+/// Checks if decompressed data consists of a chain of texture blocks.
+///
+/// Each block has a one-byte type and a little-endian `u32` size. The chain must
+/// cover the whole data or end with a block of type `0`.
+pub(crate) fn is_texture_data(data: &[u8]) -> bool {
+    if data.is_empty() || !TEXTURE_TAGS.contains(&data[0]) {
+        return false;
+    }
+
+    let mut offset = 0;
+    while offset + 5 <= data.len() {
+        let tag = data[offset];
+        let size = LittleEndian::read_u32(&data[offset + 1..offset + 5]) as usize;
+        if tag == 0 {
+            return true;
+        }
+        offset = match (offset + 5).checked_add(size) {
+            Some(o) => o,
+            None => return false,
+        };
+    }
+
+    offset == data.len()
+}
+
+/// Checks if data looks like plain csv text.
+///
+/// The first line must be valid UTF-8 without control characters and contain a comma.
+fn is_csv(data: &[u8]) -> bool {
+    let line = match data.iter().position(|&b| b == b'\n') {
+        Some(end) => &data[..end],
+        None => data,
+    };
+
+    match std::str::from_utf8(line) {
+        Ok(line) => {
+            line.contains(',')
+                && !line
+                    .chars()
+                    .any(|c| c.is_control() && c != '\r' && c != '\t')
+        }
+        Err(_) => false,
+    }
+}
+
+/// Classifies decompressed data.
+fn classify_decompressed(data: &[u8]) -> Option<FileKind> {
+    if is_texture_data(data) {
+        Some(FileKind::ScTexture)
+    } else if is_csv(data) {
+        Some(FileKind::CompressedCsv)
+    } else {
+        None
+    }
+}
+
+/// Checks whether `data` is a `PVR` image, of either version.
+pub(crate) fn is_pvr(data: &[u8]) -> bool {
+    data.starts_with(&PVR_MAGIC) || data.get(44..48) == Some(&PVR_LEGACY_MAGIC[..])
+}
+
+/// Type of a file detected by [`detect_file`], along with the data read to detect it.
+///
+/// The header and decompressed data can be reused to process the file without
+/// decompressing it again.
+///
+/// [`detect_file`]: fn.detect_file.html
+#[derive(Debug)]
+pub struct Detected {
+    /// Type of the file.
+    pub kind: FileKind,
+    /// Header of `.sc` container files.
+    pub header: Option<ScHeader>,
+    /// Decompressed data of compressed files, without the header. It is `None` if
+    /// the file is not compressed or couldn't be decompressed.
+    pub decompressed: Option<Vec<u8>>,
+}
+
+impl Detected {
+    /// Creates a detection result for a file which was not decompressed.
+    fn kind(kind: FileKind) -> Self {
+        Self {
+            kind,
+            header: None,
+            decompressed: None,
+        }
+    }
+}
+
+/// Detects the type of a file from its contents.
+///
+/// Compressed data is decompressed to tell textures, objects and csv files apart,
+/// so this function can take as long as decoding the file. See [`detect_file`]
+/// to keep the decompressed data.
+///
+/// [`detect_file`]: fn.detect_file.html
+///
+/// ## Arguments
+///
+/// * `data`: Raw file data.
+pub fn detect(data: &[u8]) -> FileKind {
+    detect_file(data).kind
+}
+
+/// Detects the type of a file from its contents, keeping the header and the
+/// decompressed data.
+///
+/// Works as [`detect`], but also returns what was read to detect the type, so
+/// that the file can be processed without decompressing it again.
+///
+/// [`detect`]: fn.detect.html
+///
+/// ## Arguments
+///
+/// * `data`: Raw file data.
+pub fn detect_file(data: &[u8]) -> Detected {
+    if data.starts_with(&KTX_MAGIC) || data.starts_with(&KTX2_MAGIC) {
+        return Detected::kind(FileKind::Ktx);
+    }
+    if is_pvr(data) {
+        return Detected::kind(FileKind::Pvr);
+    }
+
+    if is_container(data) {
+        let header = match ScHeader::parse(data) {
+            Ok(h) => h,
+            Err(_) => return Detected::kind(FileKind::Unknown),
+        };
+        return match decompress(header.payload(data)) {
+            Ok(d) => Detected {
+                kind: classify_decompressed(d.get_ref()).unwrap_or(FileKind::ScObject),
+                header: Some(header),
+                decompressed: Some(d.into_inner()),
+            },
+            Err(_) => Detected {
+                kind: FileKind::Sc,
+                header: Some(header),
+                decompressed: None,
+            },
+        };
+    }
+
+    if let Some(compression) = Compression::detect(data) {
+        return match decompress(data) {
+            Ok(d) => Detected {
+                kind: match classify_decompressed(d.get_ref()) {
+                    Some(FileKind::CompressedCsv) => FileKind::CompressedCsv,
+                    _ => FileKind::Compressed(compression),
+                },
+                header: None,
+                decompressed: Some(d.into_inner()),
+            },
+            Err(_) => Detected::kind(FileKind::Compressed(compression)),
+        };
+    }
+
+    if is_csv(data) {
+        Detected::kind(FileKind::Csv)
+    } else {
+        Detected::kind(FileKind::Unknown)
+    }
+}
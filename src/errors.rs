@@ -1,7 +1,133 @@
 //! Errors raised during processing of files.
 
-/// Error when an unknown type of pixel is passed.
-pub struct UnknownPixel(pub String);
+use std::{fmt, io, path::PathBuf};
 
-/// Error when `_tex.sc` or `.csv` file decompression fails.
-pub struct DecompressionError(pub String);
+/// Alias for `Result` with the crate's [`Error`] type.
+///
+/// [`Error`]: enum.Error.html
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// Error raised when processing `_tex.sc` or `.csv` files fails.
+#[derive(Debug)]
+pub enum Error {
+    /// The data ended before `needed` more bytes could be read at `offset`.
+    UnexpectedEof { offset: usize, needed: usize },
+    /// The file doesn't have a valid header.
+    InvalidHeader(String),
+    /// Decompression of the file data failed.
+    Decompression(String),
+    /// The data uses a feature, such as a compression method, that is recognised
+    /// but not supported.
+    Unsupported(String),
+    /// The hash of the decompressed data doesn't match the hash in the file's header.
+    HashMismatch { expected: Vec<u8>, actual: Vec<u8> },
+    /// The data is structurally invalid, such as an image with no pixels or sizes
+    /// which overflow.
+    InvalidData(String),
+    /// A texture has an unknown type of pixel. `offset` is the position of the
+    /// texture's pixel data in the decompressed data.
+    UnknownPixel { pixel_type: u8, offset: usize },
+    /// Decompressed `.csv` data is not valid csv.
+    Csv(csv::Error),
+    /// Reading or writing a file failed.
+    Io(io::Error),
+    /// Encoding or saving an image failed.
+    Image(image::ImageError),
+    /// Encoding an animation failed.
+    Encoding(String),
+    /// Serializing `JSON` failed.
+    Json(serde_json::Error),
+    /// Processing the file at `path` failed.
+    File { path: PathBuf, source: Box<Error> },
+}
+
+impl Error {
+    /// Wraps the error with the path of the file being processed.
+    pub fn in_file<P: Into<PathBuf>>(self, path: P) -> Self {
+        Error::File {
+            path: path.into(),
+            source: Box::new(self),
+        }
+    }
+}
+
+/// Formats bytes as a lowercase hex string.
+pub(crate) fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::UnexpectedEof { offset, needed } => write!(
+                f,
+                "unexpected end of data at offset {} ({} more bytes needed)",
+                offset, needed
+            ),
+            Error::InvalidHeader(msg) => write!(f, "invalid header: {}", msg),
+            Error::Decompression(msg) => write!(f, "failed to decompress data: {}", msg),
+            Error::Unsupported(msg) => write!(f, "unsupported data: {}", msg),
+            Error::HashMismatch { expected, actual } => write!(
+                f,
+                "hash mismatch (expected {}, got {})",
+                to_hex(expected),
+                to_hex(actual)
+            ),
+            Error::InvalidData(msg) => write!(f, "invalid data: {}", msg),
+            Error::UnknownPixel { pixel_type, offset } => write!(
+                f,
+                "unknown pixel type ({}) at offset {}",
+                pixel_type, offset
+            ),
+            Error::Csv(e) => write!(f, "failed to parse csv data: {}", e),
+            Error::Io(e) => write!(f, "i/o error: {}", e),
+            Error::Image(e) => write!(f, "failed to save image: {}", e),
+            Error::Encoding(msg) => write!(f, "failed to encode animation: {}", msg),
+            Error::Json(e) => write!(f, "failed to write json: {}", e),
+            Error::File { path, source } => write!(f, "{}: {}", path.display(), source),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Csv(e) => Some(e),
+            Error::Io(e) => Some(e),
+            Error::Image(e) => Some(e),
+            Error::Json(e) => Some(e),
+            Error::File { source, .. } => Some(source.as_ref()),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(e: io::Error) -> Self {
+        Error::Io(e)
+    }
+}
+
+impl From<image::ImageError> for Error {
+    fn from(e: image::ImageError) -> Self {
+        Error::Image(e)
+    }
+}
+
+impl From<png::EncodingError> for Error {
+    fn from(e: png::EncodingError) -> Self {
+        Error::Encoding(e.to_string())
+    }
+}
+
+impl From<serde_json::Error> for Error {
+    fn from(e: serde_json::Error) -> Self {
+        Error::Json(e)
+    }
+}
+
+impl From<csv::Error> for Error {
+    fn from(e: csv::Error) -> Self {
+        Error::Csv(e)
+    }
+}
@@ -0,0 +1,548 @@
+//! Software rendering of movie clips into animated `GIF` or `APNG` images.
+
+use crate::{
+    errors::{Error, Result},
+    extractors::{
+        objects::{decode_objects, ColorTransform, Export, Matrix, MovieClip, ScObjects, Shape},
+        sprites::{draw_bitmap, sanitize_name, shape_bounds, union_bounds, Bounds},
+        tex::{decode_tex_data, Texture},
+    },
+};
+use colored::Colorize;
+use image::{
+    gif::{GifEncoder, Repeat},
+    Delay, Frame, RgbaImage,
+};
+use std::{
+    borrow::Borrow,
+    fmt, fs,
+    io::{BufWriter, Write},
+    path::Path,
+    str::FromStr,
+};
+
+/// Maximum depth of nested movie clips. Deeper children are not drawn, which also
+/// stops movie clips that contain themselves.
+pub(crate) const MAX_DEPTH: usize = 32;
+
+/// Maximum width and height of rendered movie clips and sprites.
+pub(crate) const MAX_SIZE: f32 = 8192.0;
+
+/// Frame rate used for movie clips which don't specify one.
+const DEFAULT_FPS: u8 = 24;
+
+/// Format of rendered animations.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AnimationFormat {
+    /// Animated `GIF`. Colors are quantized and transparency is binary.
+    Gif,
+    /// Animated `PNG`.
+    Apng,
+}
+
+impl AnimationFormat {
+    /// File extension of the format.
+    pub fn extension(&self) -> &'static str {
+        match self {
+            AnimationFormat::Gif => "gif",
+            AnimationFormat::Apng => "png",
+        }
+    }
+}
+
+impl FromStr for AnimationFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "gif" => Ok(AnimationFormat::Gif),
+            "apng" | "png" => Ok(AnimationFormat::Apng),
+            _ => Err(format!("unknown animation format `{}`", s)),
+        }
+    }
+}
+
+impl fmt::Display for AnimationFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AnimationFormat::Gif => write!(f, "GIF"),
+            AnimationFormat::Apng => write!(f, "APNG"),
+        }
+    }
+}
+
+/// Options for rendering movie clips.
+#[derive(Debug, Clone, Copy)]
+pub struct RenderOptions {
+    /// Scale at which the movie clips are rendered. `1.0` renders them in pixels
+    /// as defined by the file.
+    pub scale: f32,
+    /// Frame rate of the animations. If `None`, the frame rate of each movie clip is used.
+    pub fps: Option<u8>,
+}
+
+impl Default for RenderOptions {
+    fn default() -> Self {
+        Self {
+            scale: 1.0,
+            fps: None,
+        }
+    }
+}
+
+/// A rendered movie clip.
+#[derive(Debug, Clone)]
+pub struct Animation {
+    /// Id of the movie clip.
+    pub id: u16,
+    /// Export name of the movie clip.
+    pub name: Option<String>,
+    /// Frames per second.
+    pub fps: u8,
+    /// Rendered frames. All frames have the same size.
+    pub frames: Vec<RgbaImage>,
+    /// Position of the movie clip's origin in the frames, in pixels.
+    pub origin: (f32, f32),
+}
+
+impl Animation {
+    /// Encodes the animation as an animated `GIF` which loops forever.
+    ///
+    /// ## Arguments
+    ///
+    /// * `writer`: Writer to write the image to.
+    pub fn encode_gif<W: Write>(&self, writer: W) -> Result<()> {
+        encode_gif(writer, self.fps, self.frames.iter().cloned())
+    }
+
+    /// Encodes the animation as an animated `PNG` which loops forever.
+    ///
+    /// ## Arguments
+    ///
+    /// * `writer`: Writer to write the image to.
+    pub fn encode_apng<W: Write>(&self, writer: W) -> Result<()> {
+        let (width, height) = match self.frames.first() {
+            Some(f) => f.dimensions(),
+            None => return Err(Error::Encoding("animation has no frames".to_owned())),
+        };
+
+        encode_apng(
+            writer,
+            self.fps,
+            (width, height),
+            self.frames.len(),
+            &self.frames,
+        )
+    }
+
+    /// Encodes the animation in the given format.
+    ///
+    /// ## Arguments
+    ///
+    /// * `writer`: Writer to write the image to.
+    /// * `format`: Format of the image.
+    pub fn encode<W: Write>(&self, writer: W, format: AnimationFormat) -> Result<()> {
+        match format {
+            AnimationFormat::Gif => self.encode_gif(writer),
+            AnimationFormat::Apng => self.encode_apng(writer),
+        }
+    }
+}
+
+/// Encodes frames as an animated `GIF` which loops forever, as they are produced.
+fn encode_gif<W: Write>(writer: W, fps: u8, frames: impl Iterator<Item = RgbaImage>) -> Result<()> {
+    let mut encoder = GifEncoder::new(writer);
+    encoder.set_repeat(Repeat::Infinite)?;
+
+    let delay = Delay::from_numer_denom_ms(1000, fps.max(1) as u32);
+    encoder.encode_frames(frames.map(|f| Frame::from_parts(f, 0, 0, delay)))?;
+
+    Ok(())
+}
+
+/// Encodes `count` frames of the given size as an animated `PNG` which loops forever,
+/// as they are produced.
+fn encode_apng<W, I>(
+    writer: W,
+    fps: u8,
+    (width, height): (u32, u32),
+    count: usize,
+    frames: I,
+) -> Result<()>
+where
+    W: Write,
+    I: IntoIterator,
+    I::Item: Borrow<RgbaImage>,
+{
+    let mut encoder = png::Encoder::new(writer, width, height);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder.set_animated(count as u32, 0)?;
+    encoder.set_frame_delay(1, fps.max(1) as u16)?;
+
+    let mut writer = encoder.write_header()?;
+    for frame in frames {
+        writer.write_image_data(frame.borrow().as_raw())?;
+    }
+    writer.finish()?;
+
+    Ok(())
+}
+
+/// Draws objects with their transformations applied.
+pub(crate) struct Renderer<'a> {
+    pub(crate) objects: &'a ScObjects,
+    pub(crate) textures: &'a [Option<Texture>],
+}
+
+impl<'a> Renderer<'a> {
+    /// Calls `f` with every shape drawn by the object `id` in the given frame, along
+    /// with the shape's transformations.
+    ///
+    /// Children of movie clips play the same frame as their parent. Children
+    /// with fewer frames loop.
+    fn visit(
+        &self,
+        id: u16,
+        matrix: &Matrix,
+        color: &ColorTransform,
+        frame: usize,
+        depth: usize,
+        f: &mut dyn FnMut(&Shape, &Matrix, &ColorTransform),
+    ) {
+        if depth > MAX_DEPTH {
+            return;
+        }
+        if let Some(shape) = self.objects.shape(id) {
+            f(shape, matrix, color);
+            return;
+        }
+
+        let clip = match self.objects.movie_clip(id) {
+            Some(c) => c,
+            None => return,
+        };
+
+        for (child, child_matrix, child_color) in self.objects.frame_children(clip, frame) {
+            self.visit(
+                child.id,
+                &matrix.multiply(child_matrix),
+                &color.multiply(child_color),
+                frame,
+                depth + 1,
+                f,
+            );
+        }
+    }
+
+    /// Returns the bounding box of the object `id` in the given frame.
+    pub(crate) fn bounds(&self, id: u16, matrix: &Matrix, frame: usize) -> Option<Bounds> {
+        let mut bounds = None;
+        self.visit(
+            id,
+            matrix,
+            &ColorTransform::IDENTITY,
+            frame,
+            0,
+            &mut |shape, matrix, _| bounds = union_bounds(bounds, shape_bounds(shape, matrix)),
+        );
+        bounds
+    }
+
+    /// Draws the object `id` in the given frame onto `canvas`.
+    fn draw(&self, canvas: &mut RgbaImage, id: u16, matrix: &Matrix, frame: usize) {
+        let textures = self.textures;
+        self.visit(
+            id,
+            matrix,
+            &ColorTransform::IDENTITY,
+            frame,
+            0,
+            &mut |shape, matrix, color| {
+                for bitmap in &shape.bitmaps {
+                    if let Some(Some(texture)) = textures.get(bitmap.texture_index as usize) {
+                        draw_bitmap(canvas, bitmap, &texture.image, matrix, color);
+                    }
+                }
+            },
+        );
+    }
+}
+
+/// A movie clip laid out for rendering, one frame at a time.
+struct ClipRenderer<'a> {
+    renderer: Renderer<'a>,
+    clip: &'a MovieClip,
+    /// Transformation from the movie clip's space into the frames.
+    matrix: Matrix,
+    width: u32,
+    height: u32,
+}
+
+impl<'a> ClipRenderer<'a> {
+    /// Lays out a movie clip in the area it covers in any frame.
+    ///
+    /// `None` is returned if the movie clip draws nothing or is too large to render.
+    fn new(
+        objects: &'a ScObjects,
+        clip: &'a MovieClip,
+        textures: &'a [Option<Texture>],
+        scale: f32,
+    ) -> Option<Self> {
+        let renderer = Renderer { objects, textures };
+        let matrix = Matrix {
+            a: scale,
+            d: scale,
+            ..Matrix::IDENTITY
+        };
+
+        let (min_x, min_y, max_x, max_y) = (0..clip.frames.len())
+            .map(|frame| renderer.bounds(clip.id, &matrix, frame))
+            .fold(None, union_bounds)?;
+
+        let width = (max_x - min_x).ceil().max(1.0);
+        let height = (max_y - min_y).ceil().max(1.0);
+        if width > MAX_SIZE || height > MAX_SIZE {
+            return None;
+        }
+
+        Some(Self {
+            renderer,
+            clip,
+            matrix: Matrix {
+                tx: -min_x,
+                ty: -min_y,
+                ..matrix
+            },
+            width: width as u32,
+            height: height as u32,
+        })
+    }
+
+    /// Position of the movie clip's origin in the frames, in pixels.
+    fn origin(&self) -> (f32, f32) {
+        (self.matrix.tx, self.matrix.ty)
+    }
+
+    /// Renders the frames one by one.
+    fn frames(&self) -> impl Iterator<Item = RgbaImage> + '_ {
+        (0..self.clip.frames.len()).map(move |frame| {
+            let mut canvas = RgbaImage::new(self.width, self.height);
+            self.renderer
+                .draw(&mut canvas, self.clip.id, &self.matrix, frame);
+            canvas
+        })
+    }
+}
+
+/// Returns the frame rate at which a movie clip is rendered.
+fn clip_fps(clip: &MovieClip, options: &RenderOptions) -> u8 {
+    match options.fps.unwrap_or(clip.fps) {
+        0 => DEFAULT_FPS,
+        fps => fps,
+    }
+}
+
+/// Renders all frames of a movie clip.
+///
+/// The frames are cropped to the area covered by the movie clip in any frame.
+/// `textures` are laid out as described in [`render_shape`].
+///
+/// `None` is returned if the movie clip draws nothing or is too large to render.
+///
+/// [`render_shape`]: fn.render_shape.html
+///
+/// ## Arguments
+///
+/// * `objects`: Objects of the `.sc` file.
+/// * `clip`: The movie clip to render.
+/// * `textures`: Textures of the companion `_tex.sc` file.
+/// * `scale`: Scale at which the movie clip is rendered.
+pub fn render_movie_clip(
+    objects: &ScObjects,
+    clip: &MovieClip,
+    textures: &[Option<Texture>],
+    scale: f32,
+) -> Option<Vec<RgbaImage>> {
+    ClipRenderer::new(objects, clip, textures, scale).map(|r| r.frames().collect())
+}
+
+/// Renders all exported movie clips of an object file.
+///
+/// `textures` are laid out as described in [`render_shape`]. Movie clips which
+/// can't be rendered are left out.
+///
+/// [`render_shape`]: fn.render_shape.html
+///
+/// ## Arguments
+///
+/// * `objects`: Objects of the `.sc` file.
+/// * `textures`: Textures of the companion `_tex.sc` file.
+/// * `options`: Options for rendering.
+pub fn render_animations(
+    objects: &ScObjects,
+    textures: &[Option<Texture>],
+    options: &RenderOptions,
+) -> Vec<Animation> {
+    objects
+        .exports
+        .iter()
+        .filter_map(|export| render_export(objects, export, textures, options))
+        .collect()
+}
+
+/// Renders an exported movie clip. `None` is returned if the export is not a
+/// movie clip or the movie clip can't be rendered.
+pub(crate) fn render_export(
+    objects: &ScObjects,
+    export: &Export,
+    textures: &[Option<Texture>],
+    options: &RenderOptions,
+) -> Option<Animation> {
+    let clip = objects.movie_clip(export.id)?;
+    let renderer = ClipRenderer::new(objects, clip, textures, options.scale)?;
+
+    Some(Animation {
+        id: clip.id,
+        name: Some(export.name.clone()),
+        fps: clip_fps(clip, options),
+        frames: renderer.frames().collect(),
+        origin: renderer.origin(),
+    })
+}
+
+/// Decodes and renders the exported movie clips of a `.sc` object file.
+///
+/// Errors of [`decode_objects`] and [`decode_tex_blocks`] are raised. Textures
+/// which can't be decoded are left out of the animations.
+///
+/// [`decode_objects`]: objects/fn.decode_objects.html
+/// [`decode_tex_blocks`]: fn.decode_tex_blocks.html
+///
+/// ## Arguments
+///
+/// * `data`: Raw `.sc` file data.
+/// * `tex_data`: Raw `_tex.sc` file data.
+/// * `tex_dir`: Directory containing the `_tex.sc` file, which textures stored in
+///   external `.ktx` or `.zktx` files are loaded from. Without it, these textures
+///   fail with `Error::Unsupported` and are left out like other textures which
+///   can't be decoded.
+/// * `options`: Options for rendering.
+pub fn decode_animations(
+    data: &[u8],
+    tex_data: &[u8],
+    tex_dir: Option<&Path>,
+    options: &RenderOptions,
+) -> Result<Vec<Animation>> {
+    let objects = decode_objects(data)?;
+    let textures: Vec<_> = decode_tex_data(tex_data, tex_dir)?
+        .into_iter()
+        .map(|t| t.ok())
+        .collect();
+
+    Ok(render_animations(&objects, &textures, options))
+}
+
+/// Returns the name of the file an animation of the movie clip exported as `name` is
+/// saved in. The `_anim` suffix keeps it apart from sprites and text field previews.
+fn animation_file_name(name: &str, format: AnimationFormat) -> String {
+    format!("{}_anim.{}", sanitize_name(name), format.extension())
+}
+
+/// Saves a rendered animation of an exported movie clip in `dir`, named as by
+/// [`process_animations`].
+///
+/// [`process_animations`]: fn.process_animations.html
+pub(crate) fn save_animation(
+    animation: &Animation,
+    name: &str,
+    dir: &Path,
+    format: AnimationFormat,
+) -> Result<()> {
+    let out_path = dir.join(animation_file_name(name, format));
+    let file = fs::File::create(&out_path).map_err(|e| Error::from(e).in_file(&out_path))?;
+    animation
+        .encode(BufWriter::new(file), format)
+        .map_err(|e| e.in_file(&out_path))
+}
+
+/// Processes a `.sc` object file and its companion `_tex.sc` file into animations.
+///
+/// Every exported movie clip is rendered and saved in the given format in a
+/// directory named after the file inside the output directory (`out_dir`), as
+/// `<export name>_anim.gif` or `<export name>_anim.png`. Frames are encoded as they
+/// are rendered, so only one frame is held in memory at a time.
+///
+/// If saving the animations is unsuccessful, the error is raised
+/// wrapped in `Error::File`.
+///
+/// [`decode_objects`]: objects/fn.decode_objects.html
+///
+/// ## Arguments
+///
+/// * `objects`: Objects of the `.sc` file, as returned by [`decode_objects`].
+/// * `textures`: Textures of the companion `_tex.sc` file, indexed by block.
+/// * `path`: Path to the `.sc` file. It is used to get file name.
+/// * `out_dir`: Directory to store rendered animations.
+/// * `format`: Format of the animations.
+/// * `options`: Options for rendering.
+/// * `parallelize`: Whether files are processed in parallel or not.
+pub fn process_animations(
+    objects: &ScObjects,
+    textures: &[Option<Texture>],
+    path: &Path,
+    out_dir: &Path,
+    format: AnimationFormat,
+    options: &RenderOptions,
+    parallelize: bool,
+) -> Result<()> {
+    let file_name = path.file_stem().unwrap().to_str().unwrap();
+
+    if !parallelize {
+        println!(
+            "\nRendering movie clips of {}...",
+            path.file_name().unwrap().to_str().unwrap().green().bold()
+        );
+    }
+
+    let animation_dir = out_dir.join(file_name);
+    fs::create_dir_all(&animation_dir).map_err(|e| Error::from(e).in_file(&animation_dir))?;
+
+    let mut count = 0;
+    for export in &objects.exports {
+        let clip = match objects.movie_clip(export.id) {
+            Some(c) => c,
+            None => continue,
+        };
+        let renderer = match ClipRenderer::new(objects, clip, textures, options.scale) {
+            Some(r) => r,
+            None => continue,
+        };
+
+        let out_path = animation_dir.join(animation_file_name(&export.name, format));
+        let file = fs::File::create(&out_path).map_err(|e| Error::from(e).in_file(&out_path))?;
+        let writer = BufWriter::new(file);
+        let fps = clip_fps(clip, options);
+        match format {
+            AnimationFormat::Gif => encode_gif(writer, fps, renderer.frames()),
+            AnimationFormat::Apng => encode_apng(
+                writer,
+                fps,
+                (renderer.width, renderer.height),
+                clip.frames.len(),
+                renderer.frames(),
+            ),
+        }
+        .map_err(|e| e.in_file(&out_path))?;
+        count += 1;
+    }
+
+    println!(
+        "{} movie clip(s) rendered as {} from {}",
+        count.to_string().cyan().bold(),
+        format,
+        file_name.green()
+    );
+
+    Ok(())
+}
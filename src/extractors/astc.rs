@@ -0,0 +1,709 @@
+//! Decoding of `ASTC` compressed textures.
+//!
+//! Every block takes 16 bytes, whatever its footprint. Blocks store color endpoints
+//! and interpolation weights using integer sequence encoding, where values are
+//! packed with bits, trits or quints. Only the `LDR` profile is supported; blocks
+//! using `HDR` endpoints are decoded in the error color, as GPUs do for invalid blocks.
+
+use crate::{errors::Result, utils::decode_blocks};
+use image::RgbaImage;
+
+/// Color of blocks which are invalid or use unsupported features.
+const ERROR_COLOR: [u8; 4] = [255, 0, 255, 255];
+
+/// Ranges of values which can be encoded, as `(trits, quints, bits)`. The index
+/// of a range is its quantization level.
+const RANGES: [(bool, bool, u32); 21] = [
+    (false, false, 1), // 2
+    (true, false, 0),  // 3
+    (false, false, 2), // 4
+    (false, true, 0),  // 5
+    (true, false, 1),  // 6
+    (false, false, 3), // 8
+    (false, true, 1),  // 10
+    (true, false, 2),  // 12
+    (false, false, 4), // 16
+    (false, true, 2),  // 20
+    (true, false, 3),  // 24
+    (false, false, 5), // 32
+    (false, true, 3),  // 40
+    (true, false, 4),  // 48
+    (false, false, 6), // 64
+    (false, true, 4),  // 80
+    (true, false, 5),  // 96
+    (false, false, 7), // 128
+    (false, true, 5),  // 160
+    (true, false, 6),  // 192
+    (false, false, 8), // 256
+];
+
+/// Quantization level of the smallest range used for color endpoints.
+const MIN_COLOR_LEVEL: usize = 4;
+
+/// Returns the block footprint of an `OpenGL` internal format, as stored in `KTX` images.
+pub(crate) fn block_size_of(internal_format: u32) -> Option<(u32, u32)> {
+    const FOOTPRINTS: [(u32, u32); 14] = [
+        (4, 4),
+        (5, 4),
+        (5, 5),
+        (6, 5),
+        (6, 6),
+        (8, 5),
+        (8, 6),
+        (8, 8),
+        (10, 5),
+        (10, 6),
+        (10, 8),
+        (10, 10),
+        (12, 10),
+        (12, 12),
+    ];
+
+    match internal_format {
+        // `RGBA` and `sRGB` formats.
+        0x93B0..=0x93BD => Some(FOOTPRINTS[(internal_format - 0x93B0) as usize]),
+        0x93D0..=0x93DD => Some(FOOTPRINTS[(internal_format - 0x93D0) as usize]),
+        _ => None,
+    }
+}
+
+/// Returns `count` bits of `bits` starting at `start`.
+fn read_bits(bits: u128, start: u32, count: u32) -> u32 {
+    if start >= 128 {
+        return 0;
+    }
+    ((bits >> start) & ((1 << count) - 1)) as u32
+}
+
+/// Returns the number of bits used to encode `count` values of a range.
+fn sequence_bits(count: u32, level: usize) -> u32 {
+    let (trits, quints, bits) = RANGES[level];
+    count * bits
+        + if trits {
+            (count * 8).div_ceil(5)
+        } else if quints {
+            (count * 7).div_ceil(3)
+        } else {
+            0
+        }
+}
+
+/// Decodes the five trits packed in 8 bits.
+fn decode_trits(t: u32) -> [u32; 5] {
+    let bit = |i: u32| (t >> i) & 1;
+    let (c, t4, t3) = if (t >> 2) & 7 == 7 {
+        (((t >> 5) & 7) << 2 | (t & 3), 2, 2)
+    } else if (t >> 5) & 3 == 3 {
+        (t & 0x1F, 2, bit(7))
+    } else {
+        (t & 0x1F, bit(7), (t >> 5) & 3)
+    };
+
+    let cbit = |i: u32| (c >> i) & 1;
+    let (t2, t1, t0) = if c & 3 == 3 {
+        (2, cbit(4), (cbit(3) << 1) | (cbit(2) & !cbit(3) & 1))
+    } else if (c >> 2) & 3 == 3 {
+        (2, 2, c & 3)
+    } else {
+        (
+            cbit(4),
+            (c >> 2) & 3,
+            (cbit(1) << 1) | (cbit(0) & !cbit(1) & 1),
+        )
+    };
+
+    [t0, t1, t2, t3, t4]
+}
+
+/// Decodes the three quints packed in 7 bits.
+fn decode_quints(q: u32) -> [u32; 3] {
+    let bit = |i: u32| (q >> i) & 1;
+    if (q >> 1) & 3 == 3 && (q >> 5) & 3 == 0 {
+        let q2 = (bit(0) << 2) | ((bit(4) & !bit(0) & 1) << 1) | (bit(3) & !bit(0) & 1);
+        return [4, 4, q2];
+    }
+
+    let (q2, c) = if (q >> 1) & 3 == 3 {
+        (4, (((q >> 3) & 3) << 3) | ((!(q >> 5) & 3) << 1) | bit(0))
+    } else {
+        ((q >> 5) & 3, q & 0x1F)
+    };
+    let (q1, q0) = if c & 7 == 5 {
+        (4, (c >> 3) & 3)
+    } else {
+        ((c >> 3) & 3, c & 7)
+    };
+
+    [q0, q1, q2]
+}
+
+/// Decodes `count` values of a range, encoded from bit `start` of `bits`.
+///
+/// Values are returned as `(value, bits)`, where `bits` are the low bits of the
+/// value stored as plain bits.
+fn decode_sequence(bits: u128, start: u32, count: usize, level: usize) -> Vec<(u32, u32)> {
+    let (trits, quints, bit_count) = RANGES[level];
+    let mut values = Vec::with_capacity(count);
+    let mut position = start;
+
+    let (group, packed_bits): (usize, &[u32]) = if trits {
+        (5, &[2, 2, 1, 2, 1])
+    } else if quints {
+        (3, &[3, 2, 2])
+    } else {
+        (1, &[0])
+    };
+
+    while values.len() < count {
+        let mut low_bits = [0; 5];
+        let mut packed = 0;
+        let mut packed_position = 0;
+        for (i, &size) in packed_bits.iter().enumerate() {
+            if values.len() + i >= count {
+                break;
+            }
+            low_bits[i] = read_bits(bits, position, bit_count);
+            position += bit_count;
+            packed |= read_bits(bits, position, size) << packed_position;
+            position += size;
+            packed_position += size;
+        }
+
+        let high: Vec<u32> = if trits {
+            decode_trits(packed).to_vec()
+        } else if quints {
+            decode_quints(packed).to_vec()
+        } else {
+            vec![0]
+        };
+        for i in 0..group.min(count - values.len()) {
+            values.push(((high[i] << bit_count) | low_bits[i], low_bits[i]));
+        }
+    }
+
+    values
+}
+
+/// Builds a value from a pattern of bits, where `0` is a zero bit and letters
+/// `a` to `f` are the bits of `value` from the lowest.
+fn bit_pattern(pattern: &str, value: u32) -> u32 {
+    pattern.bytes().fold(0, |result, c| {
+        let bit = match c {
+            b'0' => 0,
+            c => (value >> (c - b'a')) & 1,
+        };
+        (result << 1) | bit
+    })
+}
+
+/// Repeats the `bits` low bits of `value` until they fill `target` bits.
+fn replicate(value: u32, bits: u32, target: u32) -> u32 {
+    if bits == 0 {
+        return 0;
+    }
+    let mut result = 0;
+    let mut filled = 0;
+    while filled < target {
+        result = (result << bits) | value;
+        filled += bits;
+    }
+    result >> (filled - target)
+}
+
+/// Unquantizes a color endpoint value to `0..=255`.
+fn unquantize_color((value, low): (u32, u32), level: usize) -> i32 {
+    let (trits, quints, bits) = RANGES[level];
+    if !trits && !quints {
+        return replicate(value, bits, 8) as i32;
+    }
+
+    let high = value >> bits;
+    let (pattern, c) = match (trits, bits) {
+        (true, 1) => ("000000000", 204),
+        (true, 2) => ("b000b0bb0", 93),
+        (true, 3) => ("cb000cbcb", 44),
+        (true, 4) => ("dcb000dcb", 22),
+        (true, 5) => ("edcb000ed", 11),
+        (true, _) => ("fedcb000f", 5),
+        (false, 1) => ("000000000", 113),
+        (false, 2) => ("b0000bb00", 54),
+        (false, 3) => ("cb0000cbc", 26),
+        (false, 4) => ("dcb0000dc", 13),
+        (false, _) => ("edcb0000e", 6),
+    };
+
+    let a = if low & 1 == 1 { 0x1FF } else { 0 };
+    let t = (high * c + bit_pattern(pattern, low)) ^ a;
+    ((a & 0x80) | (t >> 2)) as i32
+}
+
+/// Unquantizes a weight to `0..=64`.
+fn unquantize_weight((value, low): (u32, u32), level: usize) -> u32 {
+    let (trits, quints, bits) = RANGES[level];
+    let high = value >> bits;
+
+    let weight = if !trits && !quints {
+        replicate(value, bits, 6)
+    } else if bits == 0 {
+        if trits {
+            [0, 32, 63][high as usize]
+        } else {
+            [0, 16, 32, 47, 63][high as usize]
+        }
+    } else {
+        let (pattern, c) = match (trits, bits) {
+            (true, 1) => ("0000000", 50),
+            (true, 2) => ("b000b0b", 23),
+            (true, _) => ("cb000cb", 11),
+            (false, 1) => ("0000000", 28),
+            (false, _) => ("b0000b0", 13),
+        };
+        let a = if low & 1 == 1 { 0x7F } else { 0 };
+        let t = (high * c + bit_pattern(pattern, low)) ^ a;
+        (a & 0x20) | (t >> 2)
+    };
+
+    if weight > 32 {
+        weight + 1
+    } else {
+        weight
+    }
+}
+
+/// Layout of a block's weights.
+struct BlockMode {
+    /// Size of the grid of weights.
+    width: u32,
+    height: u32,
+    /// Whether the block has a second plane of weights for one channel.
+    dual_plane: bool,
+    /// Quantization level of the weights.
+    level: usize,
+}
+
+/// Decodes the mode of a block, returning `None` for reserved modes.
+fn decode_block_mode(mode: u32) -> Option<BlockMode> {
+    let a = (mode >> 5) & 3;
+    let mut high_precision = (mode >> 9) & 1;
+    let mut dual_plane = (mode >> 10) & 1;
+    let mut range = (mode >> 4) & 1;
+
+    let (width, height) = if mode & 3 != 0 {
+        range |= (mode & 3) << 1;
+        let b = (mode >> 7) & 3;
+        match (mode >> 2) & 3 {
+            0 => (b + 4, a + 2),
+            1 => (b + 8, a + 2),
+            2 => (a + 2, b + 8),
+            _ if mode & 0x100 != 0 => ((b & 1) + 2, a + 2),
+            _ => (a + 2, (b & 1) + 6),
+        }
+    } else {
+        range |= ((mode >> 2) & 3) << 1;
+        if (mode >> 2) & 3 == 0 {
+            return None;
+        }
+        let b = (mode >> 9) & 3;
+        match (mode >> 7) & 3 {
+            0 => (12, a + 2),
+            1 => (a + 2, 12),
+            2 => {
+                high_precision = 0;
+                dual_plane = 0;
+                (a + 6, b + 6)
+            }
+            _ => match a {
+                0 => (6, 10),
+                1 => (10, 6),
+                _ => return None,
+            },
+        }
+    };
+
+    let level = (range - 2 + 6 * high_precision) as usize;
+    let count = width * height * (dual_plane + 1);
+    let bits = sequence_bits(count, level);
+    if count > 64 || !(24..=96).contains(&bits) {
+        return None;
+    }
+
+    Some(BlockMode {
+        width,
+        height,
+        dual_plane: dual_plane == 1,
+        level,
+    })
+}
+
+/// Returns the partition of the texel at `(x, y)`, as defined by the specification.
+fn select_partition(seed: u32, x: u32, y: u32, partition_count: u32, small_block: bool) -> usize {
+    let (x, y) = if small_block {
+        (x << 1, y << 1)
+    } else {
+        (x, y)
+    };
+    let seed = seed + (partition_count - 1) * 1024;
+
+    let mut rnum = seed;
+    rnum ^= rnum >> 15;
+    rnum = rnum.wrapping_sub(rnum << 17);
+    rnum = rnum.wrapping_add(rnum << 7);
+    rnum = rnum.wrapping_add(rnum << 4);
+    rnum ^= rnum >> 5;
+    rnum = rnum.wrapping_add(rnum << 16);
+    rnum ^= rnum >> 7;
+    rnum ^= rnum >> 3;
+    rnum ^= rnum << 6;
+    rnum ^= rnum >> 17;
+
+    let mut seeds = [
+        rnum & 0xF,
+        (rnum >> 4) & 0xF,
+        (rnum >> 8) & 0xF,
+        (rnum >> 12) & 0xF,
+        (rnum >> 16) & 0xF,
+        (rnum >> 20) & 0xF,
+        (rnum >> 24) & 0xF,
+        (rnum >> 28) & 0xF,
+        (rnum >> 18) & 0xF,
+        (rnum >> 22) & 0xF,
+        (rnum >> 26) & 0xF,
+        rnum.rotate_left(2) & 0xF,
+    ];
+    for s in &mut seeds {
+        *s *= *s;
+    }
+
+    let (sh1, sh2) = if seed & 1 == 1 {
+        (
+            if seed & 2 != 0 { 4 } else { 5 },
+            if partition_count == 3 { 6 } else { 5 },
+        )
+    } else {
+        (
+            if partition_count == 3 { 6 } else { 5 },
+            if seed & 2 != 0 { 4 } else { 5 },
+        )
+    };
+    let sh3 = if seed & 0x10 != 0 { sh1 } else { sh2 };
+    for (i, s) in seeds.iter_mut().enumerate() {
+        *s >>= match i {
+            8.. => sh3,
+            i if i % 2 == 0 => sh1,
+            _ => sh2,
+        };
+    }
+
+    // Blocks are 2D, so the seeds for `z` are not used.
+    let a = (seeds[0] * x + seeds[1] * y + (rnum >> 14)) & 0x3F;
+    let b = (seeds[2] * x + seeds[3] * y + (rnum >> 10)) & 0x3F;
+    let c = if partition_count >= 3 {
+        (seeds[4] * x + seeds[5] * y + (rnum >> 6)) & 0x3F
+    } else {
+        0
+    };
+    let d = if partition_count >= 4 {
+        (seeds[6] * x + seeds[7] * y + (rnum >> 2)) & 0x3F
+    } else {
+        0
+    };
+
+    if a >= b && a >= c && a >= d {
+        0
+    } else if b >= c && b >= d {
+        1
+    } else if c >= d {
+        2
+    } else {
+        3
+    }
+}
+
+/// Moves the top bit of `b` into `a`, returning `a` as a signed 6-bit offset and
+/// the remaining 7 bits of `b` as the base.
+fn bit_transfer_signed(a: i32, b: i32) -> (i32, i32) {
+    let base = (b >> 1) | (a & 0x80);
+    let offset = (a >> 1) & 0x3F;
+    let offset = if offset & 0x20 != 0 {
+        offset - 0x40
+    } else {
+        offset
+    };
+    (offset, base)
+}
+
+/// Averages red and green with blue, undoing the blue contraction of endpoints.
+fn blue_contract([r, g, b, a]: [i32; 4]) -> [i32; 4] {
+    [(r + b) >> 1, (g + b) >> 1, b, a]
+}
+
+/// Decodes the endpoints of a color endpoint mode from their values, returning
+/// `None` for `HDR` modes.
+fn decode_endpoints(mode: u32, v: &[i32]) -> Option<[[i32; 4]; 2]> {
+    let clamp = |c: [i32; 4]| c.map(|v| v.clamp(0, 255));
+
+    Some(match mode {
+        0 => [[v[0], v[0], v[0], 255], [v[1], v[1], v[1], 255]],
+        1 => {
+            let l0 = (v[0] >> 2) | (v[1] & 0xC0);
+            let l1 = (l0 + (v[1] & 0x3F)).min(255);
+            [[l0, l0, l0, 255], [l1, l1, l1, 255]]
+        }
+        4 => [[v[0], v[0], v[0], v[2]], [v[1], v[1], v[1], v[3]]],
+        5 => {
+            let (l1, l0) = bit_transfer_signed(v[1], v[0]);
+            let (a1, a0) = bit_transfer_signed(v[3], v[2]);
+            [
+                [l0, l0, l0, a0],
+                clamp([l0 + l1, l0 + l1, l0 + l1, a0 + a1]),
+            ]
+        }
+        6 => [
+            [
+                (v[0] * v[3]) >> 8,
+                (v[1] * v[3]) >> 8,
+                (v[2] * v[3]) >> 8,
+                255,
+            ],
+            [v[0], v[1], v[2], 255],
+        ],
+        8 | 12 => {
+            let (a0, a1) = if mode == 12 { (v[6], v[7]) } else { (255, 255) };
+            if v[1] + v[3] + v[5] >= v[0] + v[2] + v[4] {
+                [[v[0], v[2], v[4], a0], [v[1], v[3], v[5], a1]]
+            } else {
+                [
+                    blue_contract([v[1], v[3], v[5], a1]),
+                    blue_contract([v[0], v[2], v[4], a0]),
+                ]
+            }
+        }
+        9 | 13 => {
+            let (r1, r0) = bit_transfer_signed(v[1], v[0]);
+            let (g1, g0) = bit_transfer_signed(v[3], v[2]);
+            let (b1, b0) = bit_transfer_signed(v[5], v[4]);
+            let (a1, a0) = if mode == 13 {
+                bit_transfer_signed(v[7], v[6])
+            } else {
+                (0, 255)
+            };
+            let base = [r0, g0, b0, a0];
+            let sum = [r0 + r1, g0 + g1, b0 + b1, a0 + a1];
+            if r1 + g1 + b1 >= 0 {
+                [base, clamp(sum)]
+            } else {
+                [clamp(blue_contract(sum)), blue_contract(base)]
+            }
+        }
+        10 => [
+            [
+                (v[0] * v[3]) >> 8,
+                (v[1] * v[3]) >> 8,
+                (v[2] * v[3]) >> 8,
+                v[4],
+            ],
+            [v[0], v[1], v[2], v[5]],
+        ],
+        _ => return None,
+    })
+}
+
+/// Infills the weights of a block from its grid of weights, for one plane.
+fn infill_weights(
+    grid: &[u32],
+    mode: &BlockMode,
+    plane: usize,
+    (block_width, block_height): (u32, u32),
+) -> Vec<u32> {
+    let planes = if mode.dual_plane { 2 } else { 1 };
+    let weight = |i: u32| grid.get(i as usize * planes + plane).copied().unwrap_or(0);
+    let ds = (1024 + block_width / 2) / (block_width - 1).max(1);
+    let dt = (1024 + block_height / 2) / (block_height - 1).max(1);
+
+    let mut weights = Vec::with_capacity((block_width * block_height) as usize);
+    for t in 0..block_height {
+        for s in 0..block_width {
+            let gs = (ds * s * (mode.width - 1) + 32) >> 6;
+            let gt = (dt * t * (mode.height - 1) + 32) >> 6;
+            let (js, fs) = (gs >> 4, gs & 0xF);
+            let (jt, ft) = (gt >> 4, gt & 0xF);
+            let v0 = js + jt * mode.width;
+
+            let w11 = (fs * ft + 8) >> 4;
+            let w10 = ft - w11;
+            let w01 = fs - w11;
+            let w00 = 16 + w11 - fs - ft;
+
+            let p = |i: u32, w: u32| if w == 0 { 0 } else { weight(i) * w };
+            weights.push(
+                (p(v0, w00)
+                    + p(v0 + 1, w01)
+                    + p(v0 + mode.width, w10)
+                    + p(v0 + mode.width + 1, w11)
+                    + 8)
+                    >> 4,
+            );
+        }
+    }
+    weights
+}
+
+/// Decodes a block into its pixels, row by row. Invalid blocks are decoded in
+/// the error color.
+fn decode_block(block: &[u8], footprint: (u32, u32), pixels: &mut [[u8; 4]]) {
+    let mut bytes = [0; 16];
+    bytes.copy_from_slice(&block[..16]);
+    let bits = u128::from_le_bytes(bytes);
+
+    if let Some(color) = decode_block_colors(bits, footprint, pixels) {
+        for pixel in pixels.iter_mut() {
+            *pixel = color;
+        }
+    }
+}
+
+/// Decodes a block into `pixels`, or returns the color of the whole block for
+/// void-extent and invalid blocks.
+fn decode_block_colors(
+    bits: u128,
+    footprint: (u32, u32),
+    pixels: &mut [[u8; 4]],
+) -> Option<[u8; 4]> {
+    let (block_width, block_height) = footprint;
+
+    // Void-extent blocks have a single color, stored as 16-bit channels.
+    if bits & 0x1FF == 0x1FC {
+        if bits & 0x200 != 0 {
+            return Some(ERROR_COLOR);
+        }
+        let channel = |i: u32| (read_bits(bits, 64 + 16 * i, 16) >> 8) as u8;
+        return Some([channel(0), channel(1), channel(2), channel(3)]);
+    }
+
+    let mode = match decode_block_mode(read_bits(bits, 0, 11)) {
+        Some(m) if m.width <= block_width && m.height <= block_height => m,
+        _ => return Some(ERROR_COLOR),
+    };
+    let partition_count = read_bits(bits, 11, 2) + 1;
+    if mode.dual_plane && partition_count == 4 {
+        return Some(ERROR_COLOR);
+    }
+
+    let weight_count = (mode.width * mode.height) as usize * if mode.dual_plane { 2 } else { 1 };
+    let weight_bits = sequence_bits(weight_count as u32, mode.level);
+    let mut below_weights = 128 - weight_bits;
+
+    // Color endpoint modes of the partitions.
+    let (modes, color_start) = if partition_count == 1 {
+        (vec![read_bits(bits, 13, 4)], 17)
+    } else {
+        let cem = read_bits(bits, 23, 6);
+        let class = cem & 3;
+        if class == 0 {
+            (vec![cem >> 2; partition_count as usize], 29)
+        } else {
+            let extra = 3 * partition_count - 4;
+            below_weights -= extra;
+            let cem = (cem | (read_bits(bits, below_weights, extra) << 6)) >> 2;
+            let modes = (0..partition_count)
+                .map(|i| {
+                    let class = ((cem >> i) & 1) + class - 1;
+                    let mode = (cem >> (partition_count + 2 * i)) & 3;
+                    (class << 2) | mode
+                })
+                .collect();
+            (modes, 29)
+        }
+    };
+    let plane_channel = if mode.dual_plane {
+        below_weights -= 2;
+        Some(read_bits(bits, below_weights, 2) as usize)
+    } else {
+        None
+    };
+
+    // Colors use the largest range which fits in the remaining bits.
+    let value_count: u32 = modes.iter().map(|m| 2 * (m / 4 + 1)).sum();
+    let color_bits = below_weights.saturating_sub(color_start);
+    let color_level = match (0..RANGES.len())
+        .rev()
+        .find(|&level| sequence_bits(value_count, level) <= color_bits)
+    {
+        Some(level) if level >= MIN_COLOR_LEVEL && value_count <= 18 => level,
+        _ => return Some(ERROR_COLOR),
+    };
+
+    let values: Vec<i32> = decode_sequence(bits, color_start, value_count as usize, color_level)
+        .into_iter()
+        .map(|v| unquantize_color(v, color_level))
+        .collect();
+    let mut endpoints = Vec::with_capacity(modes.len());
+    let mut offset = 0;
+    for &m in &modes {
+        let count = (2 * (m / 4 + 1)) as usize;
+        match decode_endpoints(m, &values[offset..offset + count]) {
+            Some(e) => endpoints.push(e),
+            None => return Some(ERROR_COLOR),
+        }
+        offset += count;
+    }
+
+    let grid: Vec<u32> = decode_sequence(bits.reverse_bits(), 0, weight_count, mode.level)
+        .into_iter()
+        .map(|w| unquantize_weight(w, mode.level))
+        .collect();
+    let planes = [
+        infill_weights(&grid, &mode, 0, footprint),
+        if mode.dual_plane {
+            infill_weights(&grid, &mode, 1, footprint)
+        } else {
+            Vec::new()
+        },
+    ];
+
+    let seed = read_bits(bits, 13, 10);
+    let small_block = block_width * block_height < 31;
+    for y in 0..block_height {
+        for x in 0..block_width {
+            let i = (y * block_width + x) as usize;
+            let partition = if partition_count > 1 {
+                select_partition(seed, x, y, partition_count, small_block)
+            } else {
+                0
+            };
+            let [e0, e1] = endpoints[partition];
+
+            for c in 0..4 {
+                let weight = match plane_channel {
+                    Some(channel) if channel == c => planes[1][i],
+                    _ => planes[0][i],
+                } as i32;
+                let (c0, c1) = (e0[c] * 257, e1[c] * 257);
+                pixels[i][c] = (((c0 * (64 - weight) + c1 * weight + 32) >> 6) >> 8) as u8;
+            }
+        }
+    }
+
+    None
+}
+
+/// Decodes an image compressed with `ASTC`.
+///
+/// If `data` is too short for the image, `Error::UnexpectedEof` is raised.
+///
+/// ## Arguments
+///
+/// * `footprint`: Width and height of the blocks in pixels.
+/// * `width`: Width of the image in pixels.
+/// * `height`: Height of the image in pixels.
+/// * `data`: Blocks of the image.
+pub(crate) fn decode_astc(
+    footprint: (u32, u32),
+    width: u32,
+    height: u32,
+    data: &[u8],
+) -> Result<RgbaImage> {
+    decode_blocks(width, height, footprint, 16, data, |block, pixels| {
+        decode_block(block, footprint, pixels)
+    })
+}
@@ -1,13 +1,69 @@
-use crate::{errors::DecompressionError, utils::decompress};
+use crate::{
+    errors::{Error, Result},
+    header::{is_container, ScHeader},
+    utils::decompress,
+};
 use colored::Colorize;
 use std::{fs, path::Path};
 
+/// Decodes encoded, raw `.csv` file data.
+///
+/// The data passed here must be **compressed/raw**. Passing uncompressed or decoded
+/// csv file data will result in `Error::Decompression`.
+///
+/// Csv files wrapped in an `SC` header, including signed ones, are supported too.
+/// The decompressed csv data is returned as bytes. Nothing is written to the disk.
+///
+/// ## Arguments
+///
+/// * `data`: Raw `.csv` file data.
+pub fn decode_csv(data: &[u8]) -> Result<Vec<u8>> {
+    let payload = if is_container(data) {
+        ScHeader::parse(data)?.payload(data)
+    } else {
+        data
+    };
+
+    Ok(decompress(payload)?.into_inner())
+}
+
+/// Decodes encoded, raw `.csv` file data and parses it into records.
+///
+/// Each record is a row of the csv file, with one `String` per column. Supercell
+/// csv files use the first row for column names and the second row for column
+/// types. These rows are returned as the first two records.
+///
+/// If decompression is unsuccessful, `Error::Decompression` is raised. If the
+/// decompressed data is not valid csv, `Error::Csv` is raised.
+///
+/// ## Arguments
+///
+/// * `data`: Raw `.csv` file data.
+pub fn decode_csv_records(data: &[u8]) -> Result<Vec<Vec<String>>> {
+    let decompressed = decode_csv(data)?;
+
+    let mut reader = csv::ReaderBuilder::new()
+        .has_headers(false)
+        .flexible(true)
+        .from_reader(decompressed.as_slice());
+
+    let mut records = Vec::new();
+    for record in reader.records() {
+        records.push(record?.iter().map(String::from).collect());
+    }
+
+    Ok(records)
+}
+
 /// Processes encoded, raw `.csv` file data.
 ///
 /// The data passed here must be **compressed/raw**. Passing uncompressed or decoded
-/// csv file data will result in `DecompressionError`.
+/// csv file data will result in `Error::Decompression`.
 ///
-/// If decompression is unsuccessful, `DecompressionError` is raised.
+/// If decompression or writing the file is unsuccessful, the error is raised wrapped
+/// in `Error::File`. See [`decode_csv`] to get the decompressed data without saving it.
+///
+/// [`decode_csv`]: fn.decode_csv.html
 ///
 /// ## Arguments
 ///
@@ -16,22 +72,28 @@ use std::{fs, path::Path};
 /// * `out_dir`: Directory to store extracted csv files.
 /// * `_parallelize`: Whether files are processed in parallel or not.
 ///   Has no effect on this function.
-pub fn process_csv(
-    data: &[u8],
-    path: &Path,
-    out_dir: &Path,
-    _parallelize: bool,
-) -> Result<(), DecompressionError> {
-    let decompressed = match decompress(data) {
-        Ok(c) => c,
-        Err(e) => return Err(e),
-    };
+pub fn process_csv(data: &[u8], path: &Path, out_dir: &Path, _parallelize: bool) -> Result<()> {
+    let decompressed = decode_csv(data).map_err(|e| e.in_file(path))?;
+
+    save_csv(&decompressed, path, out_dir)
+}
 
+/// Saves decompressed `.csv` data in the output directory (`out_dir`), as
+/// [`process_csv`] does.
+///
+/// If writing the file is unsuccessful, the error is raised wrapped in `Error::File`.
+///
+/// [`process_csv`]: fn.process_csv.html
+///
+/// ## Arguments
+///
+/// * `decompressed`: Decompressed `.csv` data.
+/// * `path`: Path to the `.csv` file. It is used to get file name.
+/// * `out_dir`: Directory to store extracted csv files.
+pub fn save_csv(decompressed: &[u8], path: &Path, out_dir: &Path) -> Result<()> {
     let file_name = path.file_name().unwrap().to_str().unwrap();
 
     println!("\nExtracting {} file...", file_name.green().bold());
 
-    fs::write(out_dir.join(file_name), decompressed.get_ref()).unwrap();
-
-    Ok(())
+    fs::write(out_dir.join(file_name), decompressed).map_err(|e| Error::from(e).in_file(path))
 }
@@ -0,0 +1,185 @@
+//! Structured dumps of the tags of `.sc` and `_tex.sc` files, for inspection.
+
+use crate::{
+    detect::is_texture_data,
+    errors::{to_hex, Result},
+    extractors::objects::{
+        read_object_header, read_object_tag, read_tag, ObjectHeader, ObjectTag, TextureInfo,
+        MOVIE_CLIP_TAGS, SHAPE_TAGS, TEXTURE_TAGS, TEXT_FIELD_TAGS,
+    },
+    header::ScHeader,
+    utils::{decompress, Reader},
+};
+use serde::Serialize;
+
+/// Header of a dumped file.
+#[derive(Debug, Clone, Serialize)]
+pub struct HeaderDump {
+    pub version: u32,
+    /// Hash of the decompressed data, as hex.
+    pub hash: String,
+    /// Signature of signed files, as hex.
+    pub signature: Option<String>,
+    /// Metadata of version 4 files, as hex.
+    pub metadata: Option<String>,
+}
+
+/// A dumped tag.
+#[derive(Debug, Clone, Serialize)]
+pub struct TagDump {
+    /// Type of the tag.
+    pub tag: u8,
+    /// Name of the type of the tag.
+    pub name: &'static str,
+    /// Position of the tag in the decompressed data.
+    pub offset: usize,
+    /// Length of the tag's body.
+    pub length: usize,
+    /// Decoded fields of known tags.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fields: Option<serde_json::Value>,
+    /// Body of tags without decoded fields, as hex.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub data: Option<String>,
+    /// Error raised when decoding the tag's fields.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Structured dump of a `.sc` or `_tex.sc` file.
+#[derive(Debug, Clone, Serialize)]
+pub struct Dump {
+    pub header: HeaderDump,
+    /// Whether the file contains textures (`_tex.sc`) or objects (`.sc`).
+    pub kind: &'static str,
+    /// Object counts and exports of `.sc` object files.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub objects: Option<serde_json::Value>,
+    pub tags: Vec<TagDump>,
+    /// Error which stopped the dump before the end of the data.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl Dump {
+    /// Returns the dump as pretty-printed `JSON`.
+    pub fn to_json(&self) -> Result<String> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+}
+
+/// Returns the name of a type of tag.
+fn tag_name(tag: u8) -> &'static str {
+    match tag {
+        t if TEXTURE_TAGS.contains(&t) => "texture",
+        t if SHAPE_TAGS.contains(&t) => "shape",
+        t if MOVIE_CLIP_TAGS.contains(&t) => "movie_clip",
+        t if TEXT_FIELD_TAGS.contains(&t) => "text_field",
+        8 => "matrix",
+        36 => "precise_matrix",
+        9 => "color_transform",
+        42 => "matrix_bank",
+        23 => "use_low_res_texture",
+        26 => "use_external_texture",
+        30 => "use_uncommon_resolution",
+        32 => "texture_postfixes",
+        _ => "unknown",
+    }
+}
+
+/// Dumps a single tag whose body is read by `body`.
+fn dump_tag(tag: u8, offset: usize, body: &mut Reader, textures: &mut Vec<TextureInfo>) -> TagDump {
+    let length = body.len();
+    let name = tag_name(tag);
+    let mut dump = TagDump {
+        tag,
+        name,
+        offset,
+        length,
+        fields: None,
+        data: None,
+        error: None,
+    };
+
+    let raw = body.remaining().to_vec();
+    if name != "unknown" {
+        match read_object_tag(body, tag, textures) {
+            Ok(ObjectTag::Other) => (),
+            Ok(decoded) => {
+                if let ObjectTag::Texture(texture) = &decoded {
+                    textures.push(texture.clone());
+                }
+                dump.fields = serde_json::to_value(&decoded).ok();
+            }
+            Err(e) => dump.error = Some(e.to_string()),
+        }
+    }
+
+    // Tags which are unknown, have no fields or are damaged are kept as hex.
+    if dump.fields.is_none() {
+        dump.data = Some(to_hex(&raw));
+    }
+
+    dump
+}
+
+/// Dumps the tags of compressed, raw `.sc` or `_tex.sc` file data.
+///
+/// Known tags are decoded into their fields. The bodies of other tags, including
+/// known tags without fields and damaged tags, are kept as hex. Pixel data of
+/// textures is left out.
+///
+/// If the header is invalid, `Error::InvalidHeader` or `Error::UnexpectedEof` is
+/// raised. If decompression is unsuccessful, `Error::Decompression` is raised.
+/// Damaged tags don't raise errors; they are reported in the dump instead.
+///
+/// ## Arguments
+///
+/// * `data`: Raw `.sc` or `_tex.sc` file data.
+pub fn dump_sc(data: &[u8]) -> Result<Dump> {
+    let header = ScHeader::parse(data)?;
+    let decompressed = decompress(header.payload(data))?;
+    let is_texture = is_texture_data(decompressed.get_ref());
+
+    let mut dump = Dump {
+        header: HeaderDump {
+            version: header.version,
+            hash: to_hex(&header.hash),
+            signature: header.signature.as_deref().map(to_hex),
+            metadata: header.metadata.as_deref().map(to_hex),
+        },
+        kind: if is_texture { "textures" } else { "objects" },
+        objects: None,
+        tags: Vec::new(),
+        error: None,
+    };
+
+    let mut reader = Reader::new(decompressed);
+    if !is_texture {
+        match read_object_header(&mut reader) {
+            Ok(h) => dump.objects = serde_json::to_value::<ObjectHeader>(h).ok(),
+            Err(e) => {
+                dump.error = Some(e.to_string());
+                return Ok(dump);
+            }
+        }
+    }
+
+    let mut textures = Vec::new();
+    loop {
+        let offset = reader.tell();
+        match read_tag(&mut reader) {
+            Ok(Some((tag, mut body))) => {
+                dump.tags
+                    .push(dump_tag(tag, offset, &mut body, &mut textures));
+            }
+            Ok(None) => break,
+            Err(e) => {
+                dump.error = Some(e.to_string());
+                break;
+            }
+        }
+    }
+
+    Ok(dump)
+}
@@ -0,0 +1,391 @@
+//! Decoding of `ETC1`, `ETC2` and `EAC` compressed textures.
+//!
+//! The formats split images into blocks of 4x4 pixels. Color blocks (`ETC1` and
+//! `ETC2`) and single channel blocks (`EAC`) take 8 bytes each. Pixels of a block are
+//! indexed column by column, as described by the Khronos Data Format Specification.
+
+use crate::{errors::Result, utils::decode_blocks};
+use image::RgbaImage;
+
+/// Intensity modifiers of `ETC1` subblocks, for pixel indices `0` and `1`.
+/// Indices `2` and `3` use the negated modifiers.
+const ETC1_MODIFIERS: [[i32; 2]; 8] = [
+    [2, 8],
+    [5, 17],
+    [9, 29],
+    [13, 42],
+    [18, 60],
+    [24, 80],
+    [33, 106],
+    [47, 183],
+];
+
+/// Distances between the paint colors of `ETC2` `T` and `H` mode blocks.
+const ETC2_DISTANCES: [i32; 8] = [3, 6, 11, 16, 23, 32, 41, 64];
+
+/// Modifiers of `EAC` blocks.
+const EAC_MODIFIERS: [[i32; 8]; 16] = [
+    [-3, -6, -9, -15, 2, 5, 8, 14],
+    [-3, -7, -10, -13, 2, 6, 9, 12],
+    [-2, -5, -8, -13, 1, 4, 7, 12],
+    [-2, -4, -6, -13, 1, 3, 5, 12],
+    [-3, -6, -8, -12, 2, 5, 7, 11],
+    [-3, -7, -9, -11, 2, 6, 8, 10],
+    [-4, -7, -8, -11, 3, 6, 7, 10],
+    [-3, -5, -8, -11, 2, 4, 7, 10],
+    [-2, -6, -8, -10, 1, 5, 7, 9],
+    [-2, -5, -8, -10, 1, 4, 7, 9],
+    [-2, -4, -8, -10, 1, 3, 7, 9],
+    [-2, -5, -7, -10, 1, 4, 6, 9],
+    [-3, -4, -7, -10, 2, 3, 6, 9],
+    [-1, -2, -3, -10, 0, 1, 2, 9],
+    [-4, -6, -8, -9, 3, 5, 7, 8],
+    [-3, -5, -7, -9, 2, 4, 6, 8],
+];
+
+/// A texture format of the `ETC` family.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum EtcFormat {
+    /// `ETC1` `RGB`.
+    Etc1,
+    /// `ETC2` `RGB`.
+    Etc2Rgb,
+    /// `ETC2` `RGB` with pixels which are either opaque or fully transparent.
+    Etc2PunchthroughAlpha,
+    /// `ETC2` `RGB` with an `EAC` alpha channel.
+    Etc2Rgba,
+    /// `EAC` red channel.
+    R11 { signed: bool },
+    /// `EAC` red and green channels.
+    Rg11 { signed: bool },
+}
+
+impl EtcFormat {
+    /// Returns the format of an `OpenGL` internal format, as stored in `KTX` images.
+    pub(crate) fn from_gl(internal_format: u32) -> Option<Self> {
+        Some(match internal_format {
+            0x8D64 => EtcFormat::Etc1,
+            0x9274 | 0x9275 => EtcFormat::Etc2Rgb,
+            0x9276 | 0x9277 => EtcFormat::Etc2PunchthroughAlpha,
+            0x9278 | 0x9279 => EtcFormat::Etc2Rgba,
+            0x9270 => EtcFormat::R11 { signed: false },
+            0x9271 => EtcFormat::R11 { signed: true },
+            0x9272 => EtcFormat::Rg11 { signed: false },
+            0x9273 => EtcFormat::Rg11 { signed: true },
+            _ => return None,
+        })
+    }
+
+    /// Size of a block in bytes.
+    fn block_size(&self) -> usize {
+        match self {
+            EtcFormat::Etc2Rgba | EtcFormat::Rg11 { .. } => 16,
+            _ => 8,
+        }
+    }
+}
+
+/// Expands the low `bits` bits of `value` to 8 bits by repeating the high bits.
+fn extend(value: i32, bits: u32) -> i32 {
+    let value = value & ((1 << bits) - 1);
+    (value << (8 - bits)) | (value >> (2 * bits - 8))
+}
+
+fn clamp(value: i32) -> u8 {
+    value.clamp(0, 255) as u8
+}
+
+/// Bits of a block, read as a big-endian 64-bit number.
+#[derive(Clone, Copy)]
+struct Bits(u64);
+
+impl Bits {
+    fn new(block: &[u8]) -> Self {
+        let mut bytes = [0; 8];
+        bytes.copy_from_slice(&block[..8]);
+        Bits(u64::from_be_bytes(bytes))
+    }
+
+    fn bit(self, index: u32) -> i32 {
+        ((self.0 >> index) & 1) as i32
+    }
+
+    /// Returns `length` bits whose highest bit is `high`.
+    fn field(self, high: u32, length: u32) -> i32 {
+        ((self.0 >> (high + 1 - length)) & ((1 << length) - 1)) as i32
+    }
+
+    /// Returns the 2-bit index of the pixel at `(x, y)` of a color block.
+    fn pixel_index(self, x: usize, y: usize) -> usize {
+        let k = (x * 4 + y) as u32;
+        ((self.bit(k + 16) << 1) | self.bit(k)) as usize
+    }
+}
+
+/// Adds `value` to every channel of a color.
+fn offset([r, g, b]: [i32; 3], value: i32) -> [i32; 3] {
+    [r + value, g + value, b + value]
+}
+
+/// Decodes a color block of `ETC1` or `ETC2`.
+///
+/// Blocks of the punchthrough alpha format have no individual mode. Their
+/// differential bit tells whether the block is opaque instead.
+fn decode_color_block(block: &[u8], format: EtcFormat, pixels: &mut [[u8; 4]]) {
+    let bits = Bits::new(block);
+    let punchthrough = format == EtcFormat::Etc2PunchthroughAlpha;
+    let differential = punchthrough || bits.bit(33) == 1;
+    let opaque = !punchthrough || bits.bit(33) == 1;
+    let flip = bits.bit(32) == 1;
+    let tables = [bits.field(39, 3) as usize, bits.field(36, 3) as usize];
+
+    if !differential {
+        let colors = [
+            [bits.field(63, 4), bits.field(55, 4), bits.field(47, 4)].map(|c| extend(c, 4)),
+            [bits.field(59, 4), bits.field(51, 4), bits.field(43, 4)].map(|c| extend(c, 4)),
+        ];
+        return draw_subblocks(bits, colors, tables, flip, opaque, pixels);
+    }
+
+    let base = [bits.field(63, 5), bits.field(55, 5), bits.field(47, 5)];
+    // Deltas are signed 3-bit numbers.
+    let delta = [bits.field(58, 3), bits.field(50, 3), bits.field(42, 3)].map(|d| (d << 29) >> 29);
+    let mut second = [base[0] + delta[0], base[1] + delta[1], base[2] + delta[2]];
+    if format == EtcFormat::Etc1 {
+        second = second.map(|c| c & 0x1F);
+    }
+
+    match second.iter().position(|c| !(0..32).contains(c)) {
+        None => {
+            let colors = [base.map(|c| extend(c, 5)), second.map(|c| extend(c, 5))];
+            draw_subblocks(bits, colors, tables, flip, opaque, pixels);
+        }
+        Some(0) => draw_t_mode(bits, opaque, pixels),
+        Some(1) => draw_h_mode(bits, opaque, pixels),
+        Some(_) => draw_planar(bits, pixels),
+    }
+}
+
+/// Draws a block made of two subblocks of 2x4 pixels, or 4x2 pixels if `flip` is set.
+fn draw_subblocks(
+    bits: Bits,
+    colors: [[i32; 3]; 2],
+    tables: [usize; 2],
+    flip: bool,
+    opaque: bool,
+    pixels: &mut [[u8; 4]],
+) {
+    for y in 0..4 {
+        for x in 0..4 {
+            let subblock = if flip { y / 2 } else { x / 2 };
+            let [small, large] = ETC1_MODIFIERS[tables[subblock]];
+            let modifier = match bits.pixel_index(x, y) {
+                0 if opaque => small,
+                0 => 0,
+                1 => large,
+                2 if opaque => -small,
+                2 => {
+                    pixels[y * 4 + x] = [0; 4];
+                    continue;
+                }
+                _ => -large,
+            };
+
+            let [r, g, b] = offset(colors[subblock], modifier);
+            pixels[y * 4 + x] = [clamp(r), clamp(g), clamp(b), 255];
+        }
+    }
+}
+
+/// Draws a block whose pixels each use one of four paint colors.
+fn draw_paint_colors(bits: Bits, paints: [[i32; 3]; 4], opaque: bool, pixels: &mut [[u8; 4]]) {
+    for y in 0..4 {
+        for x in 0..4 {
+            let index = bits.pixel_index(x, y);
+            pixels[y * 4 + x] = if !opaque && index == 2 {
+                [0; 4]
+            } else {
+                let [r, g, b] = paints[index];
+                [clamp(r), clamp(g), clamp(b), 255]
+            };
+        }
+    }
+}
+
+/// Draws an `ETC2` block in `T` mode.
+fn draw_t_mode(bits: Bits, opaque: bool, pixels: &mut [[u8; 4]]) {
+    let first = [
+        (bits.field(60, 2) << 2) | bits.field(57, 2),
+        bits.field(55, 4),
+        bits.field(51, 4),
+    ]
+    .map(|c| extend(c, 4));
+    let second = [bits.field(47, 4), bits.field(43, 4), bits.field(39, 4)].map(|c| extend(c, 4));
+    let distance = ETC2_DISTANCES[((bits.field(35, 2) << 1) | bits.bit(32)) as usize];
+
+    let paints = [
+        first,
+        offset(second, distance),
+        second,
+        offset(second, -distance),
+    ];
+    draw_paint_colors(bits, paints, opaque, pixels);
+}
+
+/// Draws an `ETC2` block in `H` mode.
+fn draw_h_mode(bits: Bits, opaque: bool, pixels: &mut [[u8; 4]]) {
+    let first = [
+        bits.field(62, 4),
+        (bits.field(58, 3) << 1) | bits.bit(52),
+        (bits.bit(51) << 3) | bits.field(49, 3),
+    ]
+    .map(|c| extend(c, 4));
+    let second = [bits.field(46, 4), bits.field(42, 4), bits.field(38, 4)].map(|c| extend(c, 4));
+
+    let value = |[r, g, b]: [i32; 3]| (r << 16) | (g << 8) | b;
+    let index = (bits.bit(34) << 2) | (bits.bit(32) << 1) | (value(first) >= value(second)) as i32;
+    let distance = ETC2_DISTANCES[index as usize];
+
+    let paints = [
+        offset(first, distance),
+        offset(first, -distance),
+        offset(second, distance),
+        offset(second, -distance),
+    ];
+    draw_paint_colors(bits, paints, opaque, pixels);
+}
+
+/// Draws an `ETC2` block in planar mode, where colors are interpolated between
+/// the colors at the origin (`O`), the right (`H`) and the bottom (`V`) of the block.
+fn draw_planar(bits: Bits, pixels: &mut [[u8; 4]]) {
+    let origin = [
+        extend(bits.field(62, 6), 6),
+        extend((bits.bit(56) << 6) | bits.field(54, 6), 7),
+        extend(
+            (bits.bit(48) << 5) | (bits.field(44, 2) << 3) | bits.field(41, 3),
+            6,
+        ),
+    ];
+    let horizontal = [
+        extend((bits.field(38, 5) << 1) | bits.bit(32), 6),
+        extend(bits.field(31, 7), 7),
+        extend(bits.field(24, 6), 6),
+    ];
+    let vertical = [
+        extend(bits.field(18, 6), 6),
+        extend(bits.field(12, 7), 7),
+        extend(bits.field(5, 6), 6),
+    ];
+
+    for y in 0..4 {
+        for x in 0..4 {
+            let channel = |i: usize| {
+                let (x, y) = (x as i32, y as i32);
+                clamp(
+                    (x * (horizontal[i] - origin[i])
+                        + y * (vertical[i] - origin[i])
+                        + 4 * origin[i]
+                        + 2)
+                        >> 2,
+                )
+            };
+            pixels[y * 4 + x] = [channel(0), channel(1), channel(2), 255];
+        }
+    }
+}
+
+/// Decodes an `EAC` block into the values of its pixels, row by row.
+///
+/// `value` computes a pixel's value from the block's base value, multiplier and
+/// the pixel's modifier.
+fn decode_eac_block(block: &[u8], value: impl Fn(i32, i32, i32) -> u8) -> [u8; 16] {
+    let bits = Bits::new(block);
+    let base = bits.field(63, 8);
+    let multiplier = bits.field(55, 4);
+    let modifiers = EAC_MODIFIERS[bits.field(51, 4) as usize];
+
+    let mut values = [0; 16];
+    for y in 0..4 {
+        for x in 0..4 {
+            let k = (x * 4 + y) as u32;
+            let modifier = modifiers[bits.field(47 - 3 * k, 3) as usize];
+            values[y * 4 + x] = value(base, multiplier, modifier);
+        }
+    }
+    values
+}
+
+/// Returns the 8-bit value of a pixel of an 11-bit `EAC` block.
+fn eac_11(base: i32, multiplier: i32, modifier: i32, signed: bool) -> u8 {
+    let scaled = match multiplier {
+        0 => modifier,
+        m => modifier * m * 8,
+    };
+
+    if signed {
+        // The base is a signed byte, where `-128` means `-127`.
+        let base = (base as u8 as i8).max(-127) as i32;
+        let value = (base * 8 + scaled).clamp(-1023, 1023);
+        ((value + 1023) * 255 / 2046) as u8
+    } else {
+        let value = (base * 8 + 4 + scaled).clamp(0, 2047);
+        (value * 255 / 2047) as u8
+    }
+}
+
+/// Decodes an image in an `ETC` format.
+///
+/// Channels missing from the format are filled as `OpenGL` does: `R11` and `RG11`
+/// images have no blue channel and formats without alpha are opaque.
+///
+/// If `data` is too short for the image, `Error::UnexpectedEof` is raised.
+///
+/// ## Arguments
+///
+/// * `format`: Format of the image.
+/// * `width`: Width of the image in pixels.
+/// * `height`: Height of the image in pixels.
+/// * `data`: Blocks of the image.
+pub(crate) fn decode_etc(
+    format: EtcFormat,
+    width: u32,
+    height: u32,
+    data: &[u8],
+) -> Result<RgbaImage> {
+    decode_blocks(
+        width,
+        height,
+        (4, 4),
+        format.block_size(),
+        data,
+        |block, pixels| match format {
+            EtcFormat::Etc1 | EtcFormat::Etc2Rgb | EtcFormat::Etc2PunchthroughAlpha => {
+                decode_color_block(block, format, pixels)
+            }
+            EtcFormat::Etc2Rgba => {
+                decode_color_block(&block[8..], format, pixels);
+                let alpha = decode_eac_block(block, |base, multiplier, modifier| {
+                    clamp(base + modifier * multiplier)
+                });
+                for (pixel, alpha) in pixels.iter_mut().zip(&alpha) {
+                    pixel[3] = *alpha;
+                }
+            }
+            EtcFormat::R11 { signed } | EtcFormat::Rg11 { signed } => {
+                let channel = |block: &[u8]| {
+                    decode_eac_block(block, |base, multiplier, modifier| {
+                        eac_11(base, multiplier, modifier, signed)
+                    })
+                };
+                let red = channel(block);
+                let green = match format {
+                    EtcFormat::Rg11 { .. } => channel(&block[8..]),
+                    _ => [0; 16],
+                };
+                for (i, pixel) in pixels.iter_mut().enumerate() {
+                    *pixel = [red[i], green[i], 0, 255];
+                }
+            }
+        },
+    )
+}
@@ -0,0 +1,200 @@
+//! Decoding of `KTX` textures, used by newer `_tex.sc` files.
+//!
+//! Texture tags `45` and `47` don't store pixels like older tags. Tag `45` embeds a
+//! `KTX` image and tag `47` names a `.ktx` or `.zktx` file next to the `_tex.sc`
+//! file. `.zktx` files are `KTX` images compressed with `Zstandard`.
+
+use crate::{
+    detect::{KTX2_MAGIC, KTX_MAGIC},
+    errors::{Error, Result},
+    extractors::{
+        astc::{block_size_of, decode_astc},
+        etc::{decode_etc, EtcFormat},
+        pvrtc::{decode_pvrtc, PvrtcFormat},
+        tex::PixelFormat,
+    },
+    utils::{decompress, Reader, ZSTD_MAGIC},
+};
+use image::{Rgba, RgbaImage};
+use std::io::Cursor;
+
+/// Value of the endianness field of little-endian images.
+const ENDIANNESS: u32 = 0x0403_0201;
+
+const GL_UNSIGNED_BYTE: u32 = 0x1401;
+const GL_UNSIGNED_SHORT_4_4_4_4: u32 = 0x8033;
+const GL_UNSIGNED_SHORT_5_5_5_1: u32 = 0x8034;
+const GL_UNSIGNED_SHORT_5_6_5: u32 = 0x8363;
+
+const GL_ALPHA: u32 = 0x1906;
+const GL_RGB: u32 = 0x1907;
+const GL_RGBA: u32 = 0x1908;
+const GL_LUMINANCE: u32 = 0x1909;
+const GL_LUMINANCE_ALPHA: u32 = 0x190A;
+
+/// Header of a `KTX` image.
+#[derive(Debug, Clone, Copy)]
+struct KtxHeader {
+    /// Type of the pixel data, `0` for compressed images.
+    gl_type: u32,
+    /// Format of the pixel data, `0` for compressed images.
+    gl_format: u32,
+    /// Format of the texture, which tells compressed images apart.
+    gl_internal_format: u32,
+    width: u32,
+    height: u32,
+}
+
+/// Reads the header of a `KTX` image, leaving `reader` at the size of the first
+/// mipmap level.
+fn read_header(reader: &mut Reader) -> Result<KtxHeader> {
+    let magic = reader.read(KTX_MAGIC.len())?;
+    if magic == KTX2_MAGIC {
+        return Err(Error::Unsupported("KTX2 textures".to_owned()));
+    }
+    if magic != KTX_MAGIC {
+        return Err(Error::InvalidHeader("invalid KTX identifier".to_owned()));
+    }
+    if reader.read_uint32()? != ENDIANNESS {
+        return Err(Error::Unsupported("big-endian KTX textures".to_owned()));
+    }
+
+    let gl_type = reader.read_uint32()?;
+    let _gl_type_size = reader.read_uint32()?;
+    let gl_format = reader.read_uint32()?;
+    let gl_internal_format = reader.read_uint32()?;
+    let _gl_base_internal_format = reader.read_uint32()?;
+    let width = reader.read_uint32()?;
+    let height = reader.read_uint32()?;
+    let _depth = reader.read_uint32()?;
+    let _array_elements = reader.read_uint32()?;
+    let _faces = reader.read_uint32()?;
+    let _mipmap_levels = reader.read_uint32()?;
+    let key_value_bytes = reader.read_uint32()?;
+    reader.read(key_value_bytes as usize)?;
+
+    if width == 0 || height == 0 {
+        return Err(Error::InvalidData(format!(
+            "KTX image of {}x{} pixels",
+            width, height
+        )));
+    }
+
+    Ok(KtxHeader {
+        gl_type,
+        gl_format,
+        gl_internal_format,
+        width,
+        height,
+    })
+}
+
+/// Decodes uncompressed pixel data. Rows are padded to multiples of 4 bytes.
+fn decode_uncompressed(header: &KtxHeader, data: &[u8]) -> Result<RgbaImage> {
+    let pixel_size = match (header.gl_type, header.gl_format) {
+        (GL_UNSIGNED_BYTE, GL_RGBA) => 4,
+        (GL_UNSIGNED_BYTE, GL_RGB) => 3,
+        (GL_UNSIGNED_BYTE, GL_LUMINANCE_ALPHA) => 2,
+        (GL_UNSIGNED_BYTE, GL_LUMINANCE) | (GL_UNSIGNED_BYTE, GL_ALPHA) => 1,
+        (GL_UNSIGNED_SHORT_4_4_4_4, GL_RGBA)
+        | (GL_UNSIGNED_SHORT_5_5_5_1, GL_RGBA)
+        | (GL_UNSIGNED_SHORT_5_6_5, GL_RGB) => 2,
+        (gl_type, gl_format) => {
+            return Err(Error::Unsupported(format!(
+                "KTX pixel type 0x{:04x} with format 0x{:04x}",
+                gl_type, gl_format
+            )))
+        }
+    };
+
+    let row_size = (header.width as usize)
+        .checked_mul(pixel_size)
+        .and_then(|size| size.checked_next_multiple_of(4));
+    let sizes = row_size.and_then(|r| Some((r, r.checked_mul(header.height as usize)?)));
+    let (row_size, needed) = match sizes {
+        Some(s) => s,
+        None => {
+            return Err(Error::InvalidData(format!(
+                "KTX image of {}x{} pixels is too large",
+                header.width, header.height
+            )))
+        }
+    };
+    if data.len() < needed {
+        return Err(Error::UnexpectedEof {
+            offset: data.len(),
+            needed: needed - data.len(),
+        });
+    }
+
+    let mut image = RgbaImage::new(header.width, header.height);
+    for (y, row) in data
+        .chunks(row_size)
+        .take(header.height as usize)
+        .enumerate()
+    {
+        for x in 0..header.width as usize {
+            let p = &row[x * pixel_size..(x + 1) * pixel_size];
+            let pixel = match (header.gl_type, header.gl_format) {
+                (GL_UNSIGNED_BYTE, GL_RGBA) => [p[0], p[1], p[2], p[3]],
+                (GL_UNSIGNED_BYTE, GL_RGB) => [p[0], p[1], p[2], 255],
+                (GL_UNSIGNED_BYTE, GL_LUMINANCE_ALPHA) => [p[0], p[0], p[0], p[1]],
+                (GL_UNSIGNED_BYTE, GL_LUMINANCE) => [p[0], p[0], p[0], 255],
+                (GL_UNSIGNED_BYTE, _) => [255, 255, 255, p[0]],
+                // Packed pixels are laid out as in `_tex.sc` textures, and `p` holds
+                // the two bytes they need.
+                (GL_UNSIGNED_SHORT_4_4_4_4, _) => {
+                    PixelFormat::Rgba4444.decode(p).unwrap_or_default()
+                }
+                (GL_UNSIGNED_SHORT_5_5_5_1, _) => {
+                    PixelFormat::Rgba5551.decode(p).unwrap_or_default()
+                }
+                _ => PixelFormat::Rgb565.decode(p).unwrap_or_default(),
+            };
+            image.put_pixel(x as u32, y as u32, Rgba(pixel));
+        }
+    }
+
+    Ok(image)
+}
+
+/// Decodes a `KTX` image, or a `Zstandard` compressed `KTX` image (`.zktx`).
+///
+/// Only the first mipmap level of the image is decoded. Uncompressed images and
+/// images compressed with `ETC1`, `ETC2`, `EAC`, `ASTC` (`LDR` profile) or `PVRTC`
+/// are supported. For other compressed texture formats, `Error::Unsupported` is raised.
+/// If the image is not a `KTX` image, `Error::InvalidHeader` is raised. If its width
+/// or height is zero, or its size overflows, `Error::InvalidData` is raised.
+///
+/// ## Arguments
+///
+/// * `data`: `KTX` or `.zktx` image data.
+pub fn decode_ktx(data: &[u8]) -> Result<RgbaImage> {
+    let data = if data.starts_with(&ZSTD_MAGIC) {
+        decompress(data)?
+    } else {
+        Cursor::new(data.to_vec())
+    };
+
+    let mut reader = Reader::new(data);
+    let header = read_header(&mut reader)?;
+    let size = reader.read_uint32()?;
+    let level = reader.read(size as usize)?;
+
+    if header.gl_type != 0 {
+        return decode_uncompressed(&header, &level);
+    }
+
+    if let Some(format) = EtcFormat::from_gl(header.gl_internal_format) {
+        decode_etc(format, header.width, header.height, &level)
+    } else if let Some(footprint) = block_size_of(header.gl_internal_format) {
+        decode_astc(footprint, header.width, header.height, &level)
+    } else if let Some(format) = PvrtcFormat::from_gl(header.gl_internal_format) {
+        decode_pvrtc(format, header.width, header.height, &level)
+    } else {
+        Err(Error::Unsupported(format!(
+            "KTX internal format 0x{:04x}",
+            header.gl_internal_format
+        )))
+    }
+}
@@ -1,2 +1,14 @@
+pub mod animation;
+pub mod astc;
 pub mod csv;
+pub mod dump;
+pub mod etc;
+pub mod ktx;
+pub mod objects;
+pub mod pvr;
+pub mod pvrtc;
+pub mod sheet;
+pub mod sprites;
+pub mod svg;
 pub mod tex;
+pub mod text;
@@ -0,0 +1,736 @@
+//! Object model of `.sc` files describing shapes, movie clips and text fields.
+//!
+//! Files like `ui.sc` or `characters.sc` don't contain any images themselves. They
+//! describe how parts of the textures in the companion `_tex.sc` file are combined
+//! into shapes, animated in movie clips and laid out with text fields.
+
+use crate::{
+    errors::Result,
+    header::ScHeader,
+    utils::{decompress, Reader},
+};
+use serde::Serialize;
+use std::io::Cursor;
+
+/// Types of texture tags.
+pub(crate) const TEXTURE_TAGS: [u8; 10] = [1, 16, 19, 24, 27, 28, 29, 34, 45, 47];
+/// Types of shape tags.
+pub(crate) const SHAPE_TAGS: [u8; 2] = [2, 18];
+/// Types of movie clip tags.
+pub(crate) const MOVIE_CLIP_TAGS: [u8; 5] = [3, 10, 12, 14, 35];
+/// Types of text field tags.
+pub(crate) const TEXT_FIELD_TAGS: [u8; 8] = [7, 15, 20, 21, 25, 33, 43, 44];
+
+/// Index used by frame elements without a matrix or color transform.
+const NO_INDEX: u16 = 0xFFFF;
+
+/// An exported object. Only exported objects can be used directly by the game.
+#[derive(Debug, Clone, Serialize)]
+pub struct Export {
+    /// Id of the exported shape or movie clip.
+    pub id: u16,
+    /// Name of the export.
+    pub name: String,
+}
+
+/// A texture described by the file. Its pixels are stored in the `_tex.sc` file.
+#[derive(Debug, Clone, Serialize)]
+pub struct TextureInfo {
+    /// Type of the texture tag.
+    pub file_type: u8,
+    /// Pixel format of the texture.
+    pub sub_type: u8,
+    /// Width of the texture in pixels.
+    pub width: u16,
+    /// Height of the texture in pixels.
+    pub height: u16,
+}
+
+/// A polygonal region of a texture drawn by a shape.
+///
+/// `uv` are the corners of the region in the texture, in pixels. `xy` are the
+/// positions where the corners are drawn, in pixels relative to the shape's origin.
+#[derive(Debug, Clone, Serialize)]
+pub struct ShapeBitmap {
+    /// Index of the texture the region is taken from.
+    pub texture_index: u8,
+    /// Positions of the corners on the screen.
+    pub xy: Vec<(f32, f32)>,
+    /// Positions of the corners in the texture.
+    pub uv: Vec<(f32, f32)>,
+}
+
+/// A shape, made of one or more texture regions.
+#[derive(Debug, Clone, Serialize)]
+pub struct Shape {
+    /// Id of the shape.
+    pub id: u16,
+    /// Texture regions drawn by the shape.
+    pub bitmaps: Vec<ShapeBitmap>,
+}
+
+/// A 2x3 affine transformation matrix.
+///
+/// A point `(x, y)` is transformed to `(a * x + c * y + tx, b * x + d * y + ty)`.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct Matrix {
+    pub a: f32,
+    pub b: f32,
+    pub c: f32,
+    pub d: f32,
+    pub tx: f32,
+    pub ty: f32,
+}
+
+impl Matrix {
+    /// The identity matrix.
+    pub const IDENTITY: Matrix = Matrix {
+        a: 1.0,
+        b: 0.0,
+        c: 0.0,
+        d: 1.0,
+        tx: 0.0,
+        ty: 0.0,
+    };
+
+    /// Transforms a point.
+    pub fn apply(&self, (x, y): (f32, f32)) -> (f32, f32) {
+        (
+            self.a * x + self.c * y + self.tx,
+            self.b * x + self.d * y + self.ty,
+        )
+    }
+
+    /// Returns the matrix which applies `other` first and then `self`.
+    pub fn multiply(&self, other: &Matrix) -> Matrix {
+        Matrix {
+            a: self.a * other.a + self.c * other.b,
+            b: self.b * other.a + self.d * other.b,
+            c: self.a * other.c + self.c * other.d,
+            d: self.b * other.c + self.d * other.d,
+            tx: self.a * other.tx + self.c * other.ty + self.tx,
+            ty: self.b * other.tx + self.d * other.ty + self.ty,
+        }
+    }
+}
+
+/// A color transformation.
+///
+/// Each color channel is multiplied by its `mul` value (where `255` is `1.0`)
+/// and then its `add` value is added. Alpha is only multiplied.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+pub struct ColorTransform {
+    pub r_add: u8,
+    pub g_add: u8,
+    pub b_add: u8,
+    pub a_mul: u8,
+    pub r_mul: u8,
+    pub g_mul: u8,
+    pub b_mul: u8,
+}
+
+impl ColorTransform {
+    /// The color transformation which doesn't change colors.
+    pub const IDENTITY: ColorTransform = ColorTransform {
+        r_add: 0,
+        g_add: 0,
+        b_add: 0,
+        a_mul: 255,
+        r_mul: 255,
+        g_mul: 255,
+        b_mul: 255,
+    };
+
+    /// Returns the color transformation which applies `other` first and then `self`.
+    pub fn multiply(&self, other: &ColorTransform) -> ColorTransform {
+        let mul = |a: u8, b: u8| (a as u16 * b as u16 / 255) as u8;
+        let add = |mul: u8, add_a: u8, add_b: u8| {
+            (add_a as u16 + add_b as u16 * mul as u16 / 255).min(255) as u8
+        };
+
+        ColorTransform {
+            r_add: add(self.r_mul, self.r_add, other.r_add),
+            g_add: add(self.g_mul, self.g_add, other.g_add),
+            b_add: add(self.b_mul, self.b_add, other.b_add),
+            a_mul: mul(self.a_mul, other.a_mul),
+            r_mul: mul(self.r_mul, other.r_mul),
+            g_mul: mul(self.g_mul, other.g_mul),
+            b_mul: mul(self.b_mul, other.b_mul),
+        }
+    }
+
+    /// Transforms an `RGBA` color.
+    pub fn apply(&self, [r, g, b, a]: [u8; 4]) -> [u8; 4] {
+        let channel = |value: u8, mul: u8, add: u8| {
+            (value as u16 * mul as u16 / 255 + add as u16).min(255) as u8
+        };
+
+        [
+            channel(r, self.r_mul, self.r_add),
+            channel(g, self.g_mul, self.g_add),
+            channel(b, self.b_mul, self.b_add),
+            channel(a, self.a_mul, 0),
+        ]
+    }
+}
+
+/// A bank of matrices and color transformations used by movie clips.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct MatrixBank {
+    pub matrices: Vec<Matrix>,
+    pub color_transforms: Vec<ColorTransform>,
+}
+
+/// A child of a movie clip.
+#[derive(Debug, Clone, Serialize)]
+pub struct MovieClipChild {
+    /// Id of the child's shape, movie clip or text field.
+    pub id: u16,
+    /// Blend mode used to draw the child.
+    pub blend_mode: u8,
+    /// Name of the child, used by the game to find it.
+    pub name: Option<String>,
+}
+
+/// A child drawn in a movie clip frame.
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct FrameElement {
+    /// Index of the child in the movie clip's `children`.
+    pub child_index: u16,
+    /// Index of the child's matrix in the movie clip's matrix bank.
+    pub matrix: Option<u16>,
+    /// Index of the child's color transform in the movie clip's matrix bank.
+    pub color_transform: Option<u16>,
+}
+
+/// A frame of a movie clip.
+#[derive(Debug, Clone, Serialize)]
+pub struct Frame {
+    /// Label of the frame.
+    pub label: Option<String>,
+    /// Children drawn in the frame, from bottom to top.
+    pub elements: Vec<FrameElement>,
+}
+
+/// A movie clip, an animation of shapes, text fields and other movie clips.
+#[derive(Debug, Clone, Serialize)]
+pub struct MovieClip {
+    /// Id of the movie clip.
+    pub id: u16,
+    /// Frames per second.
+    pub fps: u8,
+    /// Children used by the frames.
+    pub children: Vec<MovieClipChild>,
+    /// Frames of the movie clip.
+    pub frames: Vec<Frame>,
+    /// Index of the matrix bank used by the frames.
+    pub matrix_bank: u8,
+    /// Nine-slice scaling grid as `(x, y, width, height)`.
+    pub scaling_grid: Option<(f32, f32, f32, f32)>,
+}
+
+/// A text field.
+#[derive(Debug, Clone, Serialize)]
+pub struct TextField {
+    /// Id of the text field.
+    pub id: u16,
+    /// Type of the text field tag. Newer types have more properties.
+    pub tag: u8,
+    /// Name of the font.
+    pub font_name: Option<String>,
+    /// Color of the text as `ARGB`.
+    pub color: u32,
+    pub bold: bool,
+    pub italic: bool,
+    pub multiline: bool,
+    /// Alignment flags of the text.
+    pub align: u8,
+    /// Size of the font.
+    pub font_size: u8,
+    /// Bounds of the text field as `(left, top, right, bottom)`.
+    pub bounds: (i16, i16, i16, i16),
+    /// Whether the text is outlined.
+    pub outline: bool,
+    /// Default text of the text field.
+    pub text: Option<String>,
+    /// Whether a font of the device is used instead of `font_name`.
+    pub use_device_font: bool,
+    /// Color of the outline as `ARGB`.
+    pub outline_color: Option<u32>,
+}
+
+/// Objects described by a `.sc` file.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ScObjects {
+    /// Exported shapes and movie clips.
+    pub exports: Vec<Export>,
+    /// Textures used by the shapes.
+    pub textures: Vec<TextureInfo>,
+    pub shapes: Vec<Shape>,
+    pub movie_clips: Vec<MovieClip>,
+    pub text_fields: Vec<TextField>,
+    /// Matrix banks. All files have at least one bank.
+    pub matrix_banks: Vec<MatrixBank>,
+    /// Whether the textures are stored in a companion `_tex.sc` file.
+    pub use_external_texture: bool,
+}
+
+impl ScObjects {
+    /// Returns the export name of the object with the given id.
+    pub fn export_name(&self, id: u16) -> Option<&str> {
+        self.exports
+            .iter()
+            .find(|e| e.id == id)
+            .map(|e| e.name.as_str())
+    }
+
+    /// Returns the shape with the given id.
+    pub fn shape(&self, id: u16) -> Option<&Shape> {
+        self.shapes.iter().find(|s| s.id == id)
+    }
+
+    /// Returns the movie clip with the given id.
+    pub fn movie_clip(&self, id: u16) -> Option<&MovieClip> {
+        self.movie_clips.iter().find(|m| m.id == id)
+    }
+
+    /// Returns the text field with the given id.
+    pub fn text_field(&self, id: u16) -> Option<&TextField> {
+        self.text_fields.iter().find(|t| t.id == id)
+    }
+
+    /// Returns the children drawn in a frame of a movie clip, from bottom to top,
+    /// with their matrices and color transformations.
+    ///
+    /// Frames past the end of the movie clip loop. Elements referring to missing
+    /// children are left out, and missing matrices or color transformations are
+    /// replaced by the identity.
+    pub(crate) fn frame_children<'a>(
+        &'a self,
+        clip: &'a MovieClip,
+        frame: usize,
+    ) -> Vec<(&'a MovieClipChild, &'a Matrix, &'a ColorTransform)> {
+        if clip.frames.is_empty() {
+            return Vec::new();
+        }
+        let bank = self.matrix_banks.get(clip.matrix_bank as usize);
+
+        clip.frames[frame % clip.frames.len()]
+            .elements
+            .iter()
+            .filter_map(|element| {
+                let child = clip.children.get(element.child_index as usize)?;
+                let matrix = element
+                    .matrix
+                    .and_then(|i| bank?.matrices.get(i as usize))
+                    .unwrap_or(&Matrix::IDENTITY);
+                let color = element
+                    .color_transform
+                    .and_then(|i| bank?.color_transforms.get(i as usize))
+                    .unwrap_or(&ColorTransform::IDENTITY);
+                Some((child, matrix, color))
+            })
+            .collect()
+    }
+}
+
+/// Reads a tag's type and length, returning `None` for the end tag.
+pub(crate) fn read_tag(reader: &mut Reader) -> Result<Option<(u8, Reader)>> {
+    if reader.len() == 0 {
+        return Ok(None);
+    }
+
+    let tag = reader.read_byte()?;
+    let length = reader.read_uint32()?;
+    if tag == 0 {
+        return Ok(None);
+    }
+
+    Ok(Some((tag, reader.sub_reader(length as usize)?)))
+}
+
+/// Reads a shape's texture region.
+fn read_shape_bitmap(
+    reader: &mut Reader,
+    tag: u8,
+    textures: &[TextureInfo],
+) -> Result<ShapeBitmap> {
+    let texture_index = reader.read_byte()?;
+    let count = if tag == 4 { 4 } else { reader.read_byte()? };
+
+    let mut xy = Vec::with_capacity(count as usize);
+    for _ in 0..count {
+        xy.push((reader.read_twip()?, reader.read_twip()?));
+    }
+
+    // Tag 22 stores normalized coordinates, other tags store pixels.
+    let (u_scale, v_scale) = match (tag, textures.get(texture_index as usize)) {
+        (22, Some(t)) => (t.width as f32 / 65535.0, t.height as f32 / 65535.0),
+        (22, None) => (1.0 / 65535.0, 1.0 / 65535.0),
+        _ => (1.0, 1.0),
+    };
+
+    let mut uv = Vec::with_capacity(count as usize);
+    for _ in 0..count {
+        let u = reader.read_uint16()? as f32 * u_scale;
+        let v = reader.read_uint16()? as f32 * v_scale;
+        uv.push((u, v));
+    }
+
+    Ok(ShapeBitmap {
+        texture_index,
+        xy,
+        uv,
+    })
+}
+
+/// Reads a shape tag.
+fn read_shape(reader: &mut Reader, tag: u8, textures: &[TextureInfo]) -> Result<Shape> {
+    let id = reader.read_uint16()?;
+    let bitmap_count = reader.read_uint16()?;
+    if tag == 18 {
+        // Total number of points in all bitmaps.
+        reader.read_uint16()?;
+    }
+
+    let mut bitmaps = Vec::with_capacity(bitmap_count as usize);
+    while let Some((inner_tag, mut inner)) = read_tag(reader)? {
+        if let 4 | 17 | 22 = inner_tag {
+            bitmaps.push(read_shape_bitmap(&mut inner, inner_tag, textures)?);
+        }
+    }
+
+    Ok(Shape { id, bitmaps })
+}
+
+/// Reads a movie clip tag.
+fn read_movie_clip(reader: &mut Reader, tag: u8) -> Result<MovieClip> {
+    let id = reader.read_uint16()?;
+    let fps = reader.read_byte()?;
+    let frame_count = reader.read_uint16()?;
+
+    let element_count = reader.read_uint32()?;
+    let mut elements = Vec::new();
+    for _ in 0..element_count {
+        let child_index = reader.read_uint16()?;
+        let matrix = reader.read_uint16()?;
+        let color_transform = reader.read_uint16()?;
+        elements.push(FrameElement {
+            child_index,
+            matrix: Some(matrix).filter(|&i| i != NO_INDEX),
+            color_transform: Some(color_transform).filter(|&i| i != NO_INDEX),
+        });
+    }
+
+    let child_count = reader.read_uint16()? as usize;
+    let mut ids = Vec::with_capacity(child_count);
+    for _ in 0..child_count {
+        ids.push(reader.read_uint16()?);
+    }
+    let mut blend_modes = vec![0; child_count];
+    if tag == 12 || tag == 35 {
+        for mode in blend_modes.iter_mut() {
+            *mode = reader.read_byte()?;
+        }
+    }
+    let mut children = Vec::with_capacity(child_count);
+    for (id, blend_mode) in ids.into_iter().zip(blend_modes) {
+        children.push(MovieClipChild {
+            id,
+            blend_mode,
+            name: reader.read_ascii()?,
+        });
+    }
+
+    let mut frames = Vec::with_capacity(frame_count as usize);
+    let mut matrix_bank = 0;
+    let mut scaling_grid = None;
+    let mut elements = elements.into_iter();
+    while let Some((inner_tag, mut inner)) = read_tag(reader)? {
+        match inner_tag {
+            11 => {
+                let count = inner.read_uint16()?;
+                let label = inner.read_ascii()?;
+                frames.push(Frame {
+                    label,
+                    elements: elements.by_ref().take(count as usize).collect(),
+                });
+            }
+            31 => {
+                scaling_grid = Some((
+                    inner.read_twip()?,
+                    inner.read_twip()?,
+                    inner.read_twip()?,
+                    inner.read_twip()?,
+                ))
+            }
+            41 => matrix_bank = inner.read_byte()?,
+            _ => (),
+        }
+    }
+
+    Ok(MovieClip {
+        id,
+        fps,
+        children,
+        frames,
+        matrix_bank,
+        scaling_grid,
+    })
+}
+
+/// Reads a text field tag.
+///
+/// Properties added in newer types of the tag are only read if present.
+fn read_text_field(reader: &mut Reader, tag: u8) -> Result<TextField> {
+    let id = reader.read_uint16()?;
+    let font_name = reader.read_ascii()?;
+    let color = reader.read_uint32()?;
+    let bold = reader.read_bool()?;
+    let italic = reader.read_bool()?;
+    let multiline = reader.read_bool()?;
+    reader.read_bool()?;
+    let align = reader.read_byte()?;
+    let font_size = reader.read_byte()?;
+    let bounds = (
+        reader.read_int16()?,
+        reader.read_int16()?,
+        reader.read_int16()?,
+        reader.read_int16()?,
+    );
+    let outline = reader.read_bool()?;
+    let text = reader.read_ascii()?;
+
+    let mut use_device_font = false;
+    let mut outline_color = None;
+    if tag != 7 && reader.len() > 0 {
+        use_device_font = reader.read_bool()?;
+        if tag > 20 && reader.len() >= 4 {
+            outline_color = Some(reader.read_uint32()?);
+        }
+    }
+
+    Ok(TextField {
+        id,
+        tag,
+        font_name,
+        color,
+        bold,
+        italic,
+        multiline,
+        align,
+        font_size,
+        bounds,
+        outline,
+        text,
+        use_device_font,
+        outline_color,
+    })
+}
+
+/// Reads a matrix tag. Tag 36 stores the matrix with higher precision.
+fn read_matrix(reader: &mut Reader, tag: u8) -> Result<Matrix> {
+    let divisor = if tag == 36 { 65535.0 } else { 1024.0 };
+
+    Ok(Matrix {
+        a: reader.read_int32()? as f32 / divisor,
+        b: reader.read_int32()? as f32 / divisor,
+        c: reader.read_int32()? as f32 / divisor,
+        d: reader.read_int32()? as f32 / divisor,
+        tx: reader.read_twip()?,
+        ty: reader.read_twip()?,
+    })
+}
+
+/// Reads a color transform tag.
+fn read_color_transform(reader: &mut Reader) -> Result<ColorTransform> {
+    Ok(ColorTransform {
+        r_add: reader.read_byte()?,
+        g_add: reader.read_byte()?,
+        b_add: reader.read_byte()?,
+        a_mul: reader.read_byte()?,
+        r_mul: reader.read_byte()?,
+        g_mul: reader.read_byte()?,
+        b_mul: reader.read_byte()?,
+    })
+}
+
+/// Counts of objects and exports at the start of `.sc` object files.
+#[derive(Debug, Clone, Serialize)]
+pub(crate) struct ObjectHeader {
+    pub shape_count: u16,
+    pub movie_clip_count: u16,
+    pub texture_count: u16,
+    pub text_field_count: u16,
+    pub matrix_count: u16,
+    pub color_transform_count: u16,
+    pub exports: Vec<Export>,
+}
+
+/// Reads the header of decompressed `.sc` object data.
+pub(crate) fn read_object_header(reader: &mut Reader) -> Result<ObjectHeader> {
+    let shape_count = reader.read_uint16()?;
+    let movie_clip_count = reader.read_uint16()?;
+    let texture_count = reader.read_uint16()?;
+    let text_field_count = reader.read_uint16()?;
+    let matrix_count = reader.read_uint16()?;
+    let color_transform_count = reader.read_uint16()?;
+    // Unused.
+    reader.read(5)?;
+
+    let export_count = reader.read_uint16()? as usize;
+    let mut ids = Vec::with_capacity(export_count);
+    for _ in 0..export_count {
+        ids.push(reader.read_uint16()?);
+    }
+    let mut exports = Vec::with_capacity(export_count);
+    for id in ids {
+        exports.push(Export {
+            id,
+            name: reader.read_ascii()?.unwrap_or_default(),
+        });
+    }
+
+    Ok(ObjectHeader {
+        shape_count,
+        movie_clip_count,
+        texture_count,
+        text_field_count,
+        matrix_count,
+        color_transform_count,
+        exports,
+    })
+}
+
+/// A decoded tag of a `.sc` object file.
+#[derive(Debug, Clone, Serialize)]
+#[serde(untagged)]
+pub(crate) enum ObjectTag {
+    Texture(TextureInfo),
+    Shape(Shape),
+    MovieClip(MovieClip),
+    TextField(TextField),
+    Matrix(Matrix),
+    ColorTransform(ColorTransform),
+    MatrixBank {
+        matrix_count: u16,
+        color_transform_count: u16,
+    },
+    /// A tag without fields or of unknown type.
+    Other,
+}
+
+/// Reads the body of a tag of type `tag`.
+///
+/// `textures` are the textures read so far, used to scale the coordinates of shapes.
+pub(crate) fn read_object_tag(
+    reader: &mut Reader,
+    tag: u8,
+    textures: &[TextureInfo],
+) -> Result<ObjectTag> {
+    Ok(match tag {
+        t if TEXTURE_TAGS.contains(&t) => {
+            // `KTX` textures start with the length of the embedded image or the
+            // name of the file storing it.
+            match t {
+                45 => {
+                    reader.read_uint32()?;
+                }
+                47 => {
+                    reader.read_ascii()?;
+                }
+                _ => (),
+            }
+            ObjectTag::Texture(TextureInfo {
+                file_type: t,
+                sub_type: reader.read_byte()?,
+                width: reader.read_uint16()?,
+                height: reader.read_uint16()?,
+            })
+        }
+        t if SHAPE_TAGS.contains(&t) => ObjectTag::Shape(read_shape(reader, t, textures)?),
+        t if MOVIE_CLIP_TAGS.contains(&t) => ObjectTag::MovieClip(read_movie_clip(reader, t)?),
+        t if TEXT_FIELD_TAGS.contains(&t) => ObjectTag::TextField(read_text_field(reader, t)?),
+        8 | 36 => ObjectTag::Matrix(read_matrix(reader, tag)?),
+        9 => ObjectTag::ColorTransform(read_color_transform(reader)?),
+        42 => ObjectTag::MatrixBank {
+            matrix_count: reader.read_uint16()?,
+            color_transform_count: reader.read_uint16()?,
+        },
+        _ => ObjectTag::Other,
+    })
+}
+
+/// Parses decompressed `.sc` object data, such as the data returned by [`detect_file`].
+///
+/// Works as [`decode_objects`] on data which is already decompressed. If a tag
+/// is truncated, `Error::UnexpectedEof` is raised.
+///
+/// [`detect_file`]: ../fn.detect_file.html
+/// [`decode_objects`]: fn.decode_objects.html
+///
+/// ## Arguments
+///
+/// * `decompressed`: Decompressed `.sc` data, without the header.
+pub fn parse_objects(decompressed: Vec<u8>) -> Result<ScObjects> {
+    let mut reader = Reader::new(Cursor::new(decompressed));
+    let header = read_object_header(&mut reader)?;
+
+    let mut objects = ScObjects {
+        exports: header.exports,
+        textures: Vec::with_capacity(header.texture_count as usize),
+        shapes: Vec::with_capacity(header.shape_count as usize),
+        movie_clips: Vec::with_capacity(header.movie_clip_count as usize),
+        text_fields: Vec::with_capacity(header.text_field_count as usize),
+        matrix_banks: vec![MatrixBank {
+            matrices: Vec::with_capacity(header.matrix_count as usize),
+            color_transforms: Vec::with_capacity(header.color_transform_count as usize),
+        }],
+        use_external_texture: false,
+    };
+
+    while let Some((tag, mut tag_reader)) = read_tag(&mut reader)? {
+        let bank = objects.matrix_banks.last_mut().unwrap();
+        match read_object_tag(&mut tag_reader, tag, &objects.textures)? {
+            ObjectTag::Texture(texture) => objects.textures.push(texture),
+            ObjectTag::Shape(shape) => objects.shapes.push(shape),
+            ObjectTag::MovieClip(clip) => objects.movie_clips.push(clip),
+            ObjectTag::TextField(text_field) => objects.text_fields.push(text_field),
+            ObjectTag::Matrix(matrix) => bank.matrices.push(matrix),
+            ObjectTag::ColorTransform(color) => bank.color_transforms.push(color),
+            ObjectTag::MatrixBank {
+                matrix_count,
+                color_transform_count,
+            } => objects.matrix_banks.push(MatrixBank {
+                matrices: Vec::with_capacity(matrix_count as usize),
+                color_transforms: Vec::with_capacity(color_transform_count as usize),
+            }),
+            ObjectTag::Other if tag == 26 => objects.use_external_texture = true,
+            ObjectTag::Other => (),
+        }
+    }
+
+    Ok(objects)
+}
+
+/// Decodes compressed, raw `.sc` object file data, like `ui.sc`.
+///
+/// The shapes, movie clips, text fields, matrices and color transforms described
+/// by the file are returned. Use [`decode_tex`] to decode the textures of the
+/// companion `_tex.sc` file.
+///
+/// If the header is invalid, `Error::InvalidHeader` or `Error::UnexpectedEof` is
+/// raised. If decompression is unsuccessful, `Error::Decompression` is raised. If
+/// a tag is truncated, `Error::UnexpectedEof` is raised.
+///
+/// [`decode_tex`]: ../fn.decode_tex.html
+///
+/// ## Arguments
+///
+/// * `data`: Raw `.sc` file data.
+pub fn decode_objects(data: &[u8]) -> Result<ScObjects> {
+    let header = ScHeader::parse(data)?;
+    let decompressed = decompress(header.payload(data))?;
+
+    parse_objects(decompressed.into_inner())
+}
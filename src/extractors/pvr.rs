@@ -0,0 +1,113 @@
+//! Decoding of `PVR` textures, used by older iOS builds of Supercell games.
+//!
+//! Both `PVR` version 3 images and legacy version 2 images are supported. Their
+//! textures are usually compressed with `PVRTC`.
+
+use crate::{
+    detect::{is_pvr, PVR_MAGIC},
+    errors::{Error, Result},
+    extractors::{
+        etc::{decode_etc, EtcFormat},
+        pvrtc::{decode_pvrtc, PvrtcFormat},
+    },
+    utils::Reader,
+};
+use image::RgbaImage;
+use std::io::Cursor;
+
+/// Size of the header of legacy `PVR` images.
+const LEGACY_HEADER_SIZE: u32 = 52;
+
+/// Compression of a `PVR` image.
+enum PvrFormat {
+    Pvrtc(PvrtcFormat),
+    Etc(EtcFormat),
+}
+
+impl PvrFormat {
+    /// Returns the format of a `PVR` (version 3) pixel format.
+    fn from_pixel_format(pixel_format: u64) -> Option<Self> {
+        let pvrtc = |two_bits, alpha| Some(PvrFormat::Pvrtc(PvrtcFormat { two_bits, alpha }));
+        match pixel_format {
+            0 => pvrtc(true, false),
+            1 => pvrtc(true, true),
+            2 => pvrtc(false, false),
+            3 => pvrtc(false, true),
+            6 => Some(PvrFormat::Etc(EtcFormat::Etc1)),
+            22 => Some(PvrFormat::Etc(EtcFormat::Etc2Rgb)),
+            23 => Some(PvrFormat::Etc(EtcFormat::Etc2Rgba)),
+            24 => Some(PvrFormat::Etc(EtcFormat::Etc2PunchthroughAlpha)),
+            25 => Some(PvrFormat::Etc(EtcFormat::R11 { signed: false })),
+            26 => Some(PvrFormat::Etc(EtcFormat::Rg11 { signed: false })),
+            _ => None,
+        }
+    }
+
+    /// Returns the format of a legacy `PVR` (version 2) pixel type.
+    fn from_legacy(pixel_type: u32, alpha: bool) -> Option<Self> {
+        let pvrtc = |two_bits| Some(PvrFormat::Pvrtc(PvrtcFormat { two_bits, alpha }));
+        match pixel_type {
+            0x0C | 0x18 => pvrtc(true),
+            0x0D | 0x19 => pvrtc(false),
+            0x36 => Some(PvrFormat::Etc(EtcFormat::Etc1)),
+            _ => None,
+        }
+    }
+}
+
+/// Decodes a `PVR` image.
+///
+/// Only the first mipmap level of the image is decoded. Images compressed with
+/// `PVRTC` (2 or 4 bits per pixel), `ETC1`, `ETC2` or `EAC` are supported. For other
+/// pixel formats, `Error::Unsupported` is raised.
+/// If the image is not a `PVR` image, `Error::InvalidHeader` is raised.
+///
+/// ## Arguments
+///
+/// * `data`: `PVR` image data.
+pub fn decode_pvr(data: &[u8]) -> Result<RgbaImage> {
+    let mut reader = Reader::new(Cursor::new(data.to_vec()));
+
+    if !is_pvr(data) {
+        return Err(Error::InvalidHeader("invalid PVR identifier".to_owned()));
+    }
+
+    let (format, width, height) = if data.starts_with(&PVR_MAGIC) {
+        reader.read(8)?;
+        let low = reader.read_uint32()? as u64;
+        let pixel_format = low | (reader.read_uint32()? as u64) << 32;
+        let _color_space = reader.read_uint32()?;
+        let _channel_type = reader.read_uint32()?;
+        let height = reader.read_uint32()?;
+        let width = reader.read_uint32()?;
+        // Depth, surfaces, faces and mipmap levels.
+        reader.read(16)?;
+        let metadata_size = reader.read_uint32()?;
+        reader.read(metadata_size as usize)?;
+
+        let format = PvrFormat::from_pixel_format(pixel_format)
+            .ok_or_else(|| Error::Unsupported(format!("PVR pixel format {}", pixel_format)))?;
+        (format, width, height)
+    } else {
+        let header_size = reader.read_uint32()?;
+        let height = reader.read_uint32()?;
+        let width = reader.read_uint32()?;
+        let _mipmap_levels = reader.read_uint32()?;
+        let pixel_type = reader.read_uint32()? & 0xFF;
+        // Data size, bits per pixel and the masks of red, green and blue.
+        reader.read(20)?;
+        let alpha_mask = reader.read_uint32()?;
+        reader.read((header_size.max(LEGACY_HEADER_SIZE) - 44) as usize)?;
+
+        let format = PvrFormat::from_legacy(pixel_type, alpha_mask != 0).ok_or_else(|| {
+            Error::Unsupported(format!("legacy PVR pixel type 0x{:02x}", pixel_type))
+        })?;
+        (format, width, height)
+    };
+
+    let level = reader.read(reader.len())?;
+    match format {
+        PvrFormat::Pvrtc(format) => decode_pvrtc(format, width, height, &level),
+        PvrFormat::Etc(format) => decode_etc(format, width, height, &level),
+    }
+}
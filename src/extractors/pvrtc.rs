@@ -0,0 +1,306 @@
+//! Decoding of `PVRTC` (version 1) compressed textures, used by older iOS games.
+//!
+//! Unlike other block formats, `PVRTC` blocks can't be decoded on their own. Each
+//! block stores two low resolution colors and the modulation of its pixels between
+//! them, and the colors of a pixel are interpolated from the four nearest blocks.
+//! Blocks are stored in Morton order, so the image must have power of two sizes.
+
+use crate::errors::{Error, Result};
+use image::{Rgba, RgbaImage};
+
+/// Modulation weights of 2-bit values, out of 8.
+const WEIGHTS: [i32; 4] = [0, 3, 5, 8];
+
+/// Modulation weights of 2-bit values in punch-through mode, where `2` also makes
+/// the pixel transparent.
+const PUNCHTHROUGH_WEIGHTS: [i32; 4] = [0, 4, 4, 8];
+
+/// A texture format of the `PVRTC` family.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct PvrtcFormat {
+    /// Whether pixels take 2 bits instead of 4, with blocks of 8x4 pixels instead of 4x4.
+    pub(crate) two_bits: bool,
+    /// Whether the alpha channel is used.
+    pub(crate) alpha: bool,
+}
+
+impl PvrtcFormat {
+    /// Returns the format of an `OpenGL` internal format, as stored in `KTX` images.
+    pub(crate) fn from_gl(internal_format: u32) -> Option<Self> {
+        let (two_bits, alpha) = match internal_format {
+            0x8C00 | 0x8A55 => (false, false),
+            0x8C01 | 0x8A54 => (true, false),
+            0x8C02 | 0x8A57 => (false, true),
+            0x8C03 | 0x8A56 => (true, true),
+            _ => return None,
+        };
+        Some(PvrtcFormat { two_bits, alpha })
+    }
+
+    /// Width of a block in pixels. Blocks are always 4 pixels high.
+    fn block_width(&self) -> u32 {
+        if self.two_bits {
+            8
+        } else {
+            4
+        }
+    }
+}
+
+/// Modulation of a pixel between the two colors of its blocks.
+#[derive(Debug, Clone, Copy)]
+enum Modulation {
+    /// Weight of the second color out of 8, and whether the pixel is transparent.
+    Weight(i32, bool),
+    /// Average of the horizontal and vertical neighbours.
+    Average,
+    /// Average of the horizontal neighbours.
+    Horizontal,
+    /// Average of the vertical neighbours.
+    Vertical,
+}
+
+/// Returns the two colors of a block, with 5 bits for red, green and blue and 4 bits for alpha.
+fn block_colors(color_data: u32) -> [[i32; 4]; 2] {
+    let a = color_data as i32 & 0xFFFF;
+    let b = (color_data >> 16) as i32;
+
+    // Opaque colors are stored as `RGB554` and `RGB555`, others as `ARGB3443` and `ARGB3444`.
+    let color_a = if a & 0x8000 != 0 {
+        [
+            (a >> 10) & 0x1F,
+            (a >> 5) & 0x1F,
+            (a & 0x1E) | ((a >> 4) & 1),
+            0xF,
+        ]
+    } else {
+        [
+            ((a >> 7) & 0x1E) | ((a >> 11) & 1),
+            ((a >> 3) & 0x1E) | ((a >> 7) & 1),
+            ((a << 1) & 0x1C) | ((a >> 2) & 3),
+            (a >> 11) & 0xE,
+        ]
+    };
+    let color_b = if b & 0x8000 != 0 {
+        [(b >> 10) & 0x1F, (b >> 5) & 0x1F, b & 0x1F, 0xF]
+    } else {
+        [
+            ((b >> 7) & 0x1E) | ((b >> 11) & 1),
+            ((b >> 3) & 0x1E) | ((b >> 7) & 1),
+            ((b << 1) & 0x1E) | ((b >> 3) & 1),
+            (b >> 11) & 0xE,
+        ]
+    };
+
+    [color_a, color_b]
+}
+
+/// Returns the modulation of the pixels of a block, row by row.
+fn block_modulation(mut bits: u32, punchthrough: bool, two_bits: bool) -> Vec<Modulation> {
+    if !two_bits {
+        let weights = if punchthrough {
+            PUNCHTHROUGH_WEIGHTS
+        } else {
+            WEIGHTS
+        };
+        return (0..16)
+            .map(|i| {
+                let value = (bits >> (2 * i)) & 3;
+                Modulation::Weight(weights[value as usize], punchthrough && value == 2)
+            })
+            .collect();
+    }
+
+    if !punchthrough {
+        return (0..32)
+            .map(|i| Modulation::Weight(if bits >> i & 1 == 1 { 8 } else { 0 }, false))
+            .collect();
+    }
+
+    // Only every other pixel is stored, with 2 bits. Others are interpolated from
+    // their neighbours. If the lowest bit is set, it tells that they are only
+    // interpolated in one direction, given by bit 20, and both bits take the value
+    // of the bit above them.
+    let interpolated = if bits & 1 == 0 {
+        Modulation::Average
+    } else if bits & (1 << 20) != 0 {
+        Modulation::Vertical
+    } else {
+        Modulation::Horizontal
+    };
+    if bits & 1 == 1 {
+        bits = (bits & !(1 << 20)) | ((bits >> 21 & 1) << 20);
+    }
+    bits = (bits & !1) | (bits >> 1 & 1);
+
+    let mut modulation = Vec::with_capacity(32);
+    for y in 0..4 {
+        for x in 0..8 {
+            if (x + y) % 2 == 0 {
+                modulation.push(Modulation::Weight(WEIGHTS[(bits & 3) as usize], false));
+                bits >>= 2;
+            } else {
+                modulation.push(interpolated);
+            }
+        }
+    }
+    modulation
+}
+
+/// Returns the index of a block in Morton order, for images whose smaller side
+/// has `min_blocks` blocks.
+fn morton_index(x: u32, y: u32, min_blocks: u32) -> usize {
+    let mut index = 0;
+    let mut shift = 0;
+    let mut mask = 1;
+    while mask < min_blocks {
+        index |= ((y & mask) | ((x & mask) << 1)) << shift;
+        mask <<= 1;
+        shift += 1;
+    }
+    (index | ((x | y) >> shift) << (2 * shift)) as usize
+}
+
+/// Decodes an image compressed with `PVRTC`.
+///
+/// If the image sizes aren't powers of two, `Error::Unsupported` is raised.
+/// If `data` is too short for the image, `Error::UnexpectedEof` is raised before
+/// anything is allocated. If the size of the image overflows, `Error::InvalidData`
+/// is raised.
+///
+/// ## Arguments
+///
+/// * `format`: Format of the image.
+/// * `width`: Width of the image in pixels.
+/// * `height`: Height of the image in pixels.
+/// * `data`: Blocks of the image.
+pub(crate) fn decode_pvrtc(
+    format: PvrtcFormat,
+    width: u32,
+    height: u32,
+    data: &[u8],
+) -> Result<RgbaImage> {
+    let (block_width, block_height) = (format.block_width(), 4);
+    // Images have at least 2x2 blocks.
+    let blocks_x = width.div_ceil(block_width).max(2);
+    let blocks_y = height.div_ceil(block_height).max(2);
+    if !blocks_x.is_power_of_two() || !blocks_y.is_power_of_two() {
+        return Err(Error::Unsupported(format!(
+            "PVRTC textures of {}x{} pixels",
+            width, height
+        )));
+    }
+
+    // Pixel indices of the padded image must fit in a `u32`.
+    let full_size = blocks_x.checked_mul(block_width).and_then(|full_width| {
+        let full_height = blocks_y.checked_mul(block_height)?;
+        full_width.checked_mul(full_height)?;
+        Some((full_width, full_height))
+    });
+    let (full_width, full_height) = match full_size {
+        Some(size) => size,
+        None => {
+            return Err(Error::InvalidData(format!(
+                "PVRTC image of {}x{} pixels is too large",
+                width, height
+            )))
+        }
+    };
+
+    // Doesn't overflow, as there are at least 16 pixels per block.
+    let needed = (blocks_x * blocks_y) as usize * 8;
+    if data.len() < needed {
+        return Err(Error::UnexpectedEof {
+            offset: data.len(),
+            needed: needed - data.len(),
+        });
+    }
+
+    // Colors of the blocks and modulation of the pixels, row by row.
+    let mut colors = vec![[[0; 4]; 2]; (blocks_x * blocks_y) as usize];
+    let mut modulation = vec![Modulation::Average; (full_width * full_height) as usize];
+    for by in 0..blocks_y {
+        for bx in 0..blocks_x {
+            let offset = morton_index(bx, by, blocks_x.min(blocks_y)) * 8;
+            let block = &data[offset..offset + 8];
+            let bits = u32::from_le_bytes([block[0], block[1], block[2], block[3]]);
+            let color_data = u32::from_le_bytes([block[4], block[5], block[6], block[7]]);
+
+            colors[(by * blocks_x + bx) as usize] = block_colors(color_data);
+            let pixels = block_modulation(bits, color_data & 1 == 1, format.two_bits);
+            for (i, m) in pixels.into_iter().enumerate() {
+                let x = bx * block_width + i as u32 % block_width;
+                let y = by * block_height + i as u32 / block_width;
+                modulation[(y * full_width + x) as usize] = m;
+            }
+        }
+    }
+
+    // Neighbours wrap around the image, and are always stored pixels.
+    let weight_at = |x: u32, y: u32| match modulation
+        [((y % full_height) * full_width + x % full_width) as usize]
+    {
+        Modulation::Weight(w, _) => w,
+        _ => 0,
+    };
+    let mut image = RgbaImage::new(width, height);
+    let scale_shift = if format.two_bits { 5 } else { 4 };
+    for y in 0..height {
+        for x in 0..width {
+            let (weight, transparent) = match modulation[(y * full_width + x) as usize] {
+                Modulation::Weight(w, transparent) => (w, transparent),
+                m => {
+                    let (left, right) = (weight_at(x + full_width - 1, y), weight_at(x + 1, y));
+                    let (up, down) = (weight_at(x, y + full_height - 1), weight_at(x, y + 1));
+                    let weight = match m {
+                        Modulation::Horizontal => (left + right + 1) / 2,
+                        Modulation::Vertical => (up + down + 1) / 2,
+                        _ => (left + right + up + down + 2) / 4,
+                    };
+                    (weight, false)
+                }
+            };
+
+            // Block colors are located at the center of their blocks.
+            let qx = (x + full_width - block_width / 2) % full_width;
+            let qy = (y + full_height - block_height / 2) % full_height;
+            let (x0, fx) = (qx / block_width, (qx % block_width) as i32);
+            let (y0, fy) = (qy / block_height, (qy % block_height) as i32);
+            let (x1, y1) = ((x0 + 1) % blocks_x, (y0 + 1) % blocks_y);
+            let (w, h) = (block_width as i32, block_height as i32);
+            let corners = [
+                (x0, y0, (w - fx) * (h - fy)),
+                (x1, y0, fx * (h - fy)),
+                (x0, y1, (w - fx) * fy),
+                (x1, y1, fx * fy),
+            ];
+
+            let mut pixel = [0; 4];
+            for (c, channel) in pixel.iter_mut().enumerate() {
+                let mut ends = [0; 2];
+                for (end, value) in ends.iter_mut().enumerate() {
+                    let v: i32 = corners
+                        .iter()
+                        .map(|&(bx, by, f)| colors[(by * blocks_x + bx) as usize][end][c] * f)
+                        .sum();
+                    // Expands 5-bit colors and 4-bit alpha to 8 bits.
+                    *value = if c == 3 {
+                        (v >> (scale_shift - 4)) + (v >> scale_shift)
+                    } else {
+                        (v >> (scale_shift - 3)) + (v >> (scale_shift + 2))
+                    };
+                }
+                *channel = ((ends[0] * (8 - weight) + ends[1] * weight) / 8) as u8;
+            }
+            if transparent {
+                pixel[3] = 0;
+            }
+            if !format.alpha {
+                pixel[3] = 255;
+            }
+            image.put_pixel(x, y, Rgba(pixel));
+        }
+    }
+
+    Ok(image)
+}
@@ -0,0 +1,338 @@
+//! Packing of rendered movie clips into sprite sheets.
+//!
+//! Sheets are described in the `JSON Hash` format of TexturePacker, which is
+//! understood by Phaser, PixiJS and most other web game frameworks.
+
+use crate::{
+    errors::{Error, Result},
+    extractors::{
+        animation::{render_export, save_animation, Animation, AnimationFormat, RenderOptions},
+        objects::ScObjects,
+        sprites::sanitize_name,
+        tex::Texture,
+    },
+};
+use colored::Colorize;
+use image::{imageops, RgbaImage};
+use serde::Serialize;
+use std::{collections::BTreeMap, fs, io::BufWriter, path::Path};
+
+/// Transparent pixels left between frames in sheets.
+const PADDING: u32 = 1;
+
+/// A rectangle in a sprite sheet.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct Rect {
+    pub x: u32,
+    pub y: u32,
+    pub w: u32,
+    pub h: u32,
+}
+
+/// A size in pixels.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct Size {
+    pub w: u32,
+    pub h: u32,
+}
+
+/// A point relative to the size of a frame, where `(0, 0)` is the top left corner
+/// and `(1, 1)` is the bottom right corner.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct Pivot {
+    pub x: f32,
+    pub y: f32,
+}
+
+/// Description of a frame in a sprite sheet.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SheetFrame {
+    /// Area of the frame in the sheet.
+    pub frame: Rect,
+    /// Whether the frame is rotated in the sheet. Frames are never rotated.
+    pub rotated: bool,
+    /// Whether transparent borders were removed from the frame.
+    pub trimmed: bool,
+    /// Area of the trimmed frame in the untrimmed frame.
+    pub sprite_source_size: Rect,
+    /// Size of the untrimmed frame.
+    pub source_size: Size,
+    /// Origin of the movie clip in the untrimmed frame.
+    pub pivot: Pivot,
+    /// Duration of the frame in milliseconds.
+    pub duration: u32,
+}
+
+/// Information about the sheet itself.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SheetMeta {
+    pub app: String,
+    pub version: String,
+    /// File name of the sheet image.
+    pub image: String,
+    pub format: String,
+    pub size: Size,
+    pub scale: String,
+}
+
+/// `JSON` description of a sprite sheet.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SheetData {
+    /// Frames by file name. Frames are named `<movie clip>_<frame index>.png`.
+    pub frames: BTreeMap<String, SheetFrame>,
+    /// Frame names of the movie clip, in order.
+    pub animations: BTreeMap<String, Vec<String>>,
+    pub meta: SheetMeta,
+}
+
+/// A sprite sheet with all frames of a movie clip.
+#[derive(Debug, Clone)]
+pub struct SpriteSheet {
+    /// The sheet image.
+    pub image: RgbaImage,
+    /// Description of the sheet.
+    pub data: SheetData,
+}
+
+impl SpriteSheet {
+    /// Returns the description of the sheet as pretty-printed `JSON`.
+    pub fn to_json(&self) -> Result<String> {
+        Ok(serde_json::to_string_pretty(&self.data)?)
+    }
+}
+
+/// Returns the area of `image` which is not fully transparent.
+///
+/// A 1x1 area is returned for fully transparent images.
+fn trim(image: &RgbaImage) -> Rect {
+    let mut bounds: Option<(u32, u32, u32, u32)> = None;
+    for (x, y, pixel) in image.enumerate_pixels() {
+        if pixel.0[3] != 0 {
+            bounds = Some(match bounds {
+                Some((x0, y0, x1, y1)) => (x0.min(x), y0.min(y), x1.max(x), y1.max(y)),
+                None => (x, y, x, y),
+            });
+        }
+    }
+
+    match bounds {
+        Some((x0, y0, x1, y1)) => Rect {
+            x: x0,
+            y: y0,
+            w: x1 - x0 + 1,
+            h: y1 - y0 + 1,
+        },
+        None => Rect {
+            x: 0,
+            y: 0,
+            w: 1,
+            h: 1,
+        },
+    }
+}
+
+/// Packs rectangles of the given sizes into rows, tallest first.
+///
+/// Returns the positions of the rectangles and the size of the sheet. The sheet
+/// is as wide as the square root of the total area, rounded up to a power of two.
+fn pack(sizes: &[Size]) -> (Vec<(u32, u32)>, Size) {
+    let area: u64 = sizes
+        .iter()
+        .map(|s| (s.w + PADDING) as u64 * (s.h + PADDING) as u64)
+        .sum();
+    let widest = sizes.iter().map(|s| s.w).max().unwrap_or(1);
+    let width = ((area as f64).sqrt().ceil() as u32)
+        .max(widest)
+        .next_power_of_two();
+
+    let mut order: Vec<_> = (0..sizes.len()).collect();
+    order.sort_by_key(|&i| std::cmp::Reverse(sizes[i].h));
+
+    let mut positions = vec![(0, 0); sizes.len()];
+    let (mut x, mut y, mut row_height, mut sheet_width) = (0, 0, 0, 0);
+    for i in order {
+        let size = sizes[i];
+        if x > 0 && x + size.w > width {
+            x = 0;
+            y += row_height + PADDING;
+            row_height = 0;
+        }
+        positions[i] = (x, y);
+        sheet_width = sheet_width.max(x + size.w);
+        row_height = row_height.max(size.h);
+        x += size.w + PADDING;
+    }
+
+    (
+        positions,
+        Size {
+            w: sheet_width.max(1),
+            h: (y + row_height).max(1),
+        },
+    )
+}
+
+/// Packs all frames of an animation into a sprite sheet.
+///
+/// Transparent borders are trimmed from the frames. The pivot of each frame is
+/// the movie clip's origin.
+///
+/// ## Arguments
+///
+/// * `animation`: The rendered movie clip.
+/// * `image_name`: File name of the sheet image, stored in the sheet's description.
+/// * `scale`: Scale at which the movie clip was rendered, stored in the sheet's description.
+pub fn pack_sheet(animation: &Animation, image_name: &str, scale: f32) -> SpriteSheet {
+    let name = match &animation.name {
+        Some(name) => name.clone(),
+        None => format!("movie_clip_{}", animation.id),
+    };
+    let duration = (1000.0 / animation.fps.max(1) as f32).round() as u32;
+
+    let trimmed: Vec<_> = animation.frames.iter().map(trim).collect();
+    let sizes: Vec<_> = trimmed.iter().map(|r| Size { w: r.w, h: r.h }).collect();
+    let (positions, size) = pack(&sizes);
+
+    let mut image = RgbaImage::new(size.w, size.h);
+    let mut frames = BTreeMap::new();
+    let mut names = Vec::with_capacity(animation.frames.len());
+    for (index, (frame, (rect, &(x, y)))) in animation
+        .frames
+        .iter()
+        .zip(trimmed.iter().zip(&positions))
+        .enumerate()
+    {
+        let cropped = imageops::crop_imm(frame, rect.x, rect.y, rect.w, rect.h).to_image();
+        imageops::replace(&mut image, &cropped, x, y);
+
+        let (width, height) = frame.dimensions();
+        let frame_name = format!("{}_{:04}.png", name, index);
+        frames.insert(
+            frame_name.clone(),
+            SheetFrame {
+                frame: Rect {
+                    x,
+                    y,
+                    w: rect.w,
+                    h: rect.h,
+                },
+                rotated: false,
+                trimmed: (rect.w, rect.h) != (width, height),
+                sprite_source_size: *rect,
+                source_size: Size {
+                    w: width,
+                    h: height,
+                },
+                pivot: Pivot {
+                    x: animation.origin.0 / width as f32,
+                    y: animation.origin.1 / height as f32,
+                },
+                duration,
+            },
+        );
+        names.push(frame_name);
+    }
+
+    let mut animations = BTreeMap::new();
+    animations.insert(name, names);
+
+    SpriteSheet {
+        image,
+        data: SheetData {
+            frames,
+            animations,
+            meta: SheetMeta {
+                app: env!("CARGO_PKG_NAME").to_owned(),
+                version: env!("CARGO_PKG_VERSION").to_owned(),
+                image: image_name.to_owned(),
+                format: "RGBA8888".to_owned(),
+                size,
+                scale: scale.to_string(),
+            },
+        },
+    }
+}
+
+/// Processes a `.sc` object file and its companion `_tex.sc` file into sprite sheets.
+///
+/// Every exported movie clip is rendered and packed into a sheet. Each sheet is
+/// saved as `<export name>_sheet.png`, described by `<export name>_sheet.json`, in a
+/// directory named after the file inside the output directory (`out_dir`).
+///
+/// If `animation_format` is given, each movie clip is also saved as an animation,
+/// as [`process_animations`] does, from the frames rendered for its sheet. Movie
+/// clips are rendered one at a time.
+///
+/// If saving the sheets is unsuccessful, the error is raised wrapped
+/// in `Error::File`.
+///
+/// [`process_animations`]: fn.process_animations.html
+/// [`decode_objects`]: objects/fn.decode_objects.html
+///
+/// ## Arguments
+///
+/// * `objects`: Objects of the `.sc` file, as returned by [`decode_objects`].
+/// * `textures`: Textures of the companion `_tex.sc` file, indexed by block.
+/// * `path`: Path to the `.sc` file. It is used to get file name.
+/// * `out_dir`: Directory to store sprite sheets.
+/// * `animation_format`: Format of the animations to save as well, if any.
+/// * `options`: Options for rendering.
+/// * `parallelize`: Whether files are processed in parallel or not.
+pub fn process_sheets(
+    objects: &ScObjects,
+    textures: &[Option<Texture>],
+    path: &Path,
+    out_dir: &Path,
+    animation_format: Option<AnimationFormat>,
+    options: &RenderOptions,
+    parallelize: bool,
+) -> Result<()> {
+    let file_name = path.file_stem().unwrap().to_str().unwrap();
+
+    if !parallelize {
+        println!(
+            "\nPacking sprite sheets of {}...",
+            path.file_name().unwrap().to_str().unwrap().green().bold()
+        );
+    }
+
+    let sheet_dir = out_dir.join(file_name);
+    fs::create_dir_all(&sheet_dir).map_err(|e| Error::from(e).in_file(&sheet_dir))?;
+
+    let mut count = 0;
+    for export in &objects.exports {
+        let animation = match render_export(objects, export, textures, options) {
+            Some(a) => a,
+            None => continue,
+        };
+
+        let name = sanitize_name(&export.name);
+        let image_name = format!("{}_sheet.png", name);
+        let sheet = pack_sheet(&animation, &image_name, options.scale);
+
+        let image_path = sheet_dir.join(&image_name);
+        sheet
+            .image
+            .save(&image_path)
+            .map_err(|e| Error::from(e).in_file(&image_path))?;
+
+        let json_path = sheet_dir.join(format!("{}_sheet.json", name));
+        let file = fs::File::create(&json_path).map_err(|e| Error::from(e).in_file(&json_path))?;
+        serde_json::to_writer_pretty(BufWriter::new(file), &sheet.data)
+            .map_err(|e| Error::from(e).in_file(&json_path))?;
+
+        if let Some(format) = animation_format {
+            save_animation(&animation, &export.name, &sheet_dir, format)?;
+        }
+        count += 1;
+    }
+
+    println!(
+        "{} sprite sheet(s) packed from {}",
+        count.to_string().cyan().bold(),
+        file_name.green()
+    );
+
+    Ok(())
+}
@@ -0,0 +1,364 @@
+//! Cutting of individual sprites out of texture atlases.
+//!
+//! The bitmaps of a shape map polygons of a texture (`uv`) to polygons on the
+//! screen (`xy`). Drawing the polygons in screen space undoes any rotation or
+//! skew applied to the regions when the atlas was packed.
+
+use crate::{
+    errors::{Error, Result},
+    extractors::{
+        animation::MAX_SIZE,
+        objects::{decode_objects, ColorTransform, Matrix, ScObjects, Shape, ShapeBitmap},
+        tex::{decode_tex_data, Texture},
+    },
+};
+use colored::Colorize;
+use image::RgbaImage;
+use std::{fs, path::Path};
+
+/// A sprite cut out of a texture atlas.
+#[derive(Debug, Clone)]
+pub struct Sprite {
+    /// Id of the shape the sprite was drawn from.
+    pub id: u16,
+    /// Export name of the shape, if it is exported.
+    pub name: Option<String>,
+    /// The sprite.
+    pub image: RgbaImage,
+}
+
+/// Bounding box as `(min_x, min_y, max_x, max_y)`.
+pub(crate) type Bounds = (f32, f32, f32, f32);
+
+/// Returns the box containing both boxes.
+pub(crate) fn union_bounds(a: Option<Bounds>, b: Option<Bounds>) -> Option<Bounds> {
+    match (a, b) {
+        (Some(a), Some(b)) => Some((a.0.min(b.0), a.1.min(b.1), a.2.max(b.2), a.3.max(b.3))),
+        (a, None) => a,
+        (None, b) => b,
+    }
+}
+
+/// Returns the bounding box of a shape transformed by `matrix`.
+pub(crate) fn shape_bounds(shape: &Shape, matrix: &Matrix) -> Option<Bounds> {
+    shape
+        .bitmaps
+        .iter()
+        .flat_map(|b| b.xy.iter())
+        .map(|&p| matrix.apply(p))
+        .fold(None, |bounds, (x, y)| {
+            union_bounds(bounds, Some((x, y, x, y)))
+        })
+}
+
+/// Returns the area of a polygon.
+fn polygon_area(points: &[(f32, f32)]) -> f32 {
+    let mut area = 0.0;
+    for (i, &(x1, y1)) in points.iter().enumerate() {
+        let (x2, y2) = points[(i + 1) % points.len()];
+        area += x1 * y2 - x2 * y1;
+    }
+    area.abs() / 2.0
+}
+
+/// Returns the scale at which a shape is drawn in the resolution of its textures.
+///
+/// Shapes are often defined at a lower resolution than the textures they use.
+fn texture_scale(shape: &Shape) -> f32 {
+    shape
+        .bitmaps
+        .iter()
+        .filter_map(|b| {
+            let xy_area = polygon_area(&b.xy);
+            if xy_area > 0.0 {
+                Some((polygon_area(&b.uv) / xy_area).sqrt())
+            } else {
+                None
+            }
+        })
+        .fold(None, |max: Option<f32>, s| {
+            Some(max.map_or(s, |m| m.max(s)))
+        })
+        .filter(|s| s.is_finite() && *s > 0.0)
+        .unwrap_or(1.0)
+}
+
+/// Draws `source` over `target`, with straight alpha.
+fn blend(target: &mut [u8], source: [u8; 4]) {
+    let src_a = source[3] as u32;
+    if src_a == 0 {
+        return;
+    }
+    let dst_a = target[3] as u32 * (255 - src_a) / 255;
+    let out_a = src_a + dst_a;
+    for i in 0..3 {
+        target[i] = ((source[i] as u32 * src_a + target[i] as u32 * dst_a) / out_a) as u8;
+    }
+    target[3] = out_a as u8;
+}
+
+/// Draws a bitmap of a shape onto `canvas`.
+///
+/// The `xy` polygon of the bitmap is transformed by `matrix` into canvas pixels and
+/// filled with the `uv` polygon of `texture`, whose colors are transformed by `color`.
+/// Texels are sampled with nearest-neighbor filtering.
+pub(crate) fn draw_bitmap(
+    canvas: &mut RgbaImage,
+    bitmap: &ShapeBitmap,
+    texture: &RgbaImage,
+    matrix: &Matrix,
+    color: &ColorTransform,
+) {
+    let points: Vec<_> = bitmap.xy.iter().map(|&p| matrix.apply(p)).collect();
+    let (canvas_width, canvas_height) = canvas.dimensions();
+    let (texture_width, texture_height) = texture.dimensions();
+    if points.len() < 3
+        || bitmap.uv.len() != points.len()
+        || texture_width == 0
+        || texture_height == 0
+    {
+        return;
+    }
+
+    // Polygons are convex, so they are drawn as a fan of triangles.
+    for i in 1..points.len() - 1 {
+        let (p0, p1, p2) = (points[0], points[i], points[i + 1]);
+        let (t0, t1, t2) = (bitmap.uv[0], bitmap.uv[i], bitmap.uv[i + 1]);
+
+        let det = (p1.1 - p2.1) * (p0.0 - p2.0) + (p2.0 - p1.0) * (p0.1 - p2.1);
+        if det.abs() < f32::EPSILON {
+            continue;
+        }
+
+        let min_x = p0.0.min(p1.0).min(p2.0).floor().max(0.0) as u32;
+        let min_y = p0.1.min(p1.1).min(p2.1).floor().max(0.0) as u32;
+        let max_x = (p0.0.max(p1.0).max(p2.0).ceil().max(0.0) as u32).min(canvas_width);
+        let max_y = (p0.1.max(p1.1).max(p2.1).ceil().max(0.0) as u32).min(canvas_height);
+
+        for y in min_y..max_y {
+            for x in min_x..max_x {
+                let (px, py) = (x as f32 + 0.5, y as f32 + 0.5);
+                let w0 = ((p1.1 - p2.1) * (px - p2.0) + (p2.0 - p1.0) * (py - p2.1)) / det;
+                let w1 = ((p2.1 - p0.1) * (px - p2.0) + (p0.0 - p2.0) * (py - p2.1)) / det;
+                let w2 = 1.0 - w0 - w1;
+                // Small tolerance so that adjacent triangles don't leave gaps.
+                if w0 < -1e-4 || w1 < -1e-4 || w2 < -1e-4 {
+                    continue;
+                }
+
+                let u = w0 * t0.0 + w1 * t1.0 + w2 * t2.0;
+                let v = w0 * t0.1 + w1 * t1.1 + w2 * t2.1;
+                let u = (u.max(0.0) as u32).min(texture_width - 1);
+                let v = (v.max(0.0) as u32).min(texture_height - 1);
+
+                let texel = color.apply(texture.get_pixel(u, v).0);
+                blend(&mut canvas.get_pixel_mut(x, y).0, texel);
+            }
+        }
+    }
+}
+
+/// Renders a shape into a sprite at the resolution of its textures.
+///
+/// `textures` are the textures of the `_tex.sc` file in the order they are stored,
+/// with `None` in place of textures which could not be decoded. Bitmaps using
+/// missing textures are not drawn.
+///
+/// `None` is returned if the shape has no bitmaps. If the sprite would be wider
+/// or higher than 8192 pixels, `Error::Unsupported` is raised.
+///
+/// ## Arguments
+///
+/// * `shape`: The shape to render.
+/// * `textures`: Textures of the `_tex.sc` file.
+pub fn render_shape(shape: &Shape, textures: &[Option<Texture>]) -> Result<Option<RgbaImage>> {
+    let scale = texture_scale(shape);
+    let matrix = Matrix {
+        a: scale,
+        d: scale,
+        ..Matrix::IDENTITY
+    };
+    let (min_x, min_y, max_x, max_y) = match shape_bounds(shape, &matrix) {
+        Some(b) => b,
+        None => return Ok(None),
+    };
+
+    let width = (max_x - min_x).ceil().max(1.0);
+    let height = (max_y - min_y).ceil().max(1.0);
+    if width > MAX_SIZE || height > MAX_SIZE {
+        return Err(Error::Unsupported(format!(
+            "shape {} is too large to render ({}x{})",
+            shape.id, width, height
+        )));
+    }
+
+    let (width, height) = (width as u32, height as u32);
+    let matrix = Matrix {
+        tx: -min_x,
+        ty: -min_y,
+        ..matrix
+    };
+
+    let mut image = RgbaImage::new(width, height);
+    for bitmap in &shape.bitmaps {
+        if let Some(Some(texture)) = textures.get(bitmap.texture_index as usize) {
+            draw_bitmap(
+                &mut image,
+                bitmap,
+                &texture.image,
+                &matrix,
+                &ColorTransform::IDENTITY,
+            );
+        }
+    }
+
+    Ok(Some(image))
+}
+
+/// Cuts all shapes of an object file out of its textures.
+///
+/// See [`render_shape`] for the layout of `textures`. Shapes without bitmaps are
+/// left out, and an `Err` is returned in place of each shape which can't be rendered.
+///
+/// [`render_shape`]: fn.render_shape.html
+///
+/// ## Arguments
+///
+/// * `objects`: Objects of the `.sc` file.
+/// * `textures`: Textures of the companion `_tex.sc` file.
+pub fn cut_sprites(objects: &ScObjects, textures: &[Option<Texture>]) -> Vec<Result<Sprite>> {
+    objects
+        .shapes
+        .iter()
+        .filter_map(|shape| {
+            let image = render_shape(shape, textures).transpose()?;
+            Some(image.map(|image| Sprite {
+                id: shape.id,
+                name: objects.export_name(shape.id).map(str::to_owned),
+                image,
+            }))
+        })
+        .collect()
+}
+
+/// Decodes the sprites of a `.sc` object file and its companion `_tex.sc` file.
+///
+/// Errors of [`decode_objects`] and [`decode_tex_blocks`] are raised. Textures
+/// which can't be decoded are left out of the sprites. Shapes which can't be
+/// rendered are returned as `Err`, as in [`cut_sprites`].
+///
+/// [`decode_objects`]: objects/fn.decode_objects.html
+/// [`decode_tex_blocks`]: fn.decode_tex_blocks.html
+/// [`cut_sprites`]: fn.cut_sprites.html
+///
+/// ## Arguments
+///
+/// * `data`: Raw `.sc` file data.
+/// * `tex_data`: Raw `_tex.sc` file data.
+/// * `tex_dir`: Directory containing the `_tex.sc` file, which textures stored in
+///   external `.ktx` or `.zktx` files are loaded from. Without it, these textures
+///   fail with `Error::Unsupported` and are left out like other textures which
+///   can't be decoded.
+pub fn decode_sprites(
+    data: &[u8],
+    tex_data: &[u8],
+    tex_dir: Option<&Path>,
+) -> Result<Vec<Result<Sprite>>> {
+    let objects = decode_objects(data)?;
+    let textures: Vec<_> = decode_tex_data(tex_data, tex_dir)?
+        .into_iter()
+        .map(|t| t.ok())
+        .collect();
+
+    Ok(cut_sprites(&objects, &textures))
+}
+
+/// Returns the file name, without extension, of an object saved by its export name
+/// followed by its id, or by `fallback` followed by its id if it has no name. The id
+/// keeps objects sharing a name apart.
+pub(crate) fn object_file_name(name: Option<&str>, fallback: &str, id: u16) -> String {
+    match name.filter(|n| !n.is_empty()) {
+        Some(name) => format!("{}_{}", sanitize_name(name), id),
+        None => format!("{}_{}", fallback, id),
+    }
+}
+
+/// Replaces characters which are not allowed in file names.
+pub(crate) fn sanitize_name(name: &str) -> String {
+    name.chars()
+        .map(|c| match c {
+            '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' => '_',
+            c if c.is_control() => '_',
+            c => c,
+        })
+        .collect()
+}
+
+/// Processes a `.sc` object file and its companion `_tex.sc` file.
+///
+/// Every shape is cut out of the textures and saved in `PNG` format in a directory
+/// named after the file inside the output directory (`out_dir`). Sprites are named
+/// by their export name followed by their shape id, as `<name>_<id>.png`, or by
+/// their shape id alone, as `shape_<id>.png`.
+///
+/// If saving the sprites is unsuccessful, the error is raised wrapped
+/// in `Error::File`. Shapes which can't be rendered are reported on `stdout`.
+///
+/// [`decode_objects`]: objects/fn.decode_objects.html
+///
+/// ## Arguments
+///
+/// * `objects`: Objects of the `.sc` file, as returned by [`decode_objects`].
+/// * `textures`: Textures of the companion `_tex.sc` file, indexed by block.
+/// * `path`: Path to the `.sc` file. It is used to get file name.
+/// * `out_dir`: Directory to store extracted sprites.
+/// * `parallelize`: Whether files are processed in parallel or not.
+pub fn process_sprites(
+    objects: &ScObjects,
+    textures: &[Option<Texture>],
+    path: &Path,
+    out_dir: &Path,
+    parallelize: bool,
+) -> Result<()> {
+    let file_name = path.file_stem().unwrap().to_str().unwrap();
+
+    if !parallelize {
+        println!(
+            "\nCutting sprites of {}...",
+            path.file_name().unwrap().to_str().unwrap().green().bold()
+        );
+    }
+
+    let sprite_dir = out_dir.join(file_name);
+    fs::create_dir_all(&sprite_dir).map_err(|e| Error::from(e).in_file(&sprite_dir))?;
+
+    let mut count = 0;
+    for sprite in cut_sprites(objects, textures) {
+        let sprite = match sprite {
+            Ok(s) => s,
+            Err(e) => {
+                println!(
+                    "{} {}",
+                    format!("Skipping sprite in {}:", file_name).red(),
+                    e.to_string().red()
+                );
+                continue;
+            }
+        };
+        let name = object_file_name(sprite.name.as_deref(), "shape", sprite.id);
+        let out_path = sprite_dir.join(format!("{}.png", name));
+        sprite
+            .image
+            .save(&out_path)
+            .map_err(|e| Error::from(e).in_file(&out_path))?;
+        count += 1;
+    }
+
+    println!(
+        "{} sprite(s) cut from {}",
+        count.to_string().cyan().bold(),
+        file_name.green()
+    );
+
+    Ok(())
+}
@@ -0,0 +1,525 @@
+//! Export of shapes and movie clips as `SVG` documents.
+//!
+//! Each bitmap of a shape becomes an `<image>` of its texture atlas, transformed
+//! from texture space onto the screen and clipped to the bitmap's polygon with a
+//! `<clipPath>`. The atlases are referenced by file name rather than embedded, so
+//! the documents use the `PNG` files saved by [`process_sc`] and can be edited in
+//! vector tools.
+//!
+//! [`process_sc`]: ../fn.process_sc.html
+
+use crate::{
+    errors::{Error, Result},
+    extractors::{
+        animation::{Renderer, MAX_DEPTH},
+        objects::{ColorTransform, Matrix, MovieClip, ScObjects, Shape, ShapeBitmap, TextField},
+        sprites::{object_file_name, shape_bounds, union_bounds, Bounds},
+        text::{align_name, to_rgba},
+    },
+};
+use colored::Colorize;
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::Write,
+    fs,
+    path::Path,
+};
+
+/// An object exported as an `SVG` document.
+#[derive(Debug, Clone)]
+pub struct SvgDocument {
+    /// Id of the shape or movie clip.
+    pub id: u16,
+    /// Export name of the object, if it is exported.
+    pub name: Option<String>,
+    /// The `SVG` document.
+    pub svg: String,
+}
+
+/// Formats a number with at most three decimals.
+fn num(value: f32) -> String {
+    ((value * 1000.0).round() / 1000.0 + 0.0).to_string()
+}
+
+/// Escapes text for use in `XML` attributes and elements.
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Formats a list of points for the `points` attribute of polygons.
+fn points(points: &[(f32, f32)]) -> String {
+    points
+        .iter()
+        .map(|&(x, y)| format!("{},{}", num(x), num(y)))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Returns the `transform` attribute of a matrix, empty for the identity.
+fn transform(matrix: &Matrix) -> String {
+    if *matrix == Matrix::IDENTITY {
+        return String::new();
+    }
+    format!(
+        r#" transform="matrix({} {} {} {} {} {})""#,
+        num(matrix.a),
+        num(matrix.b),
+        num(matrix.c),
+        num(matrix.d),
+        num(matrix.tx),
+        num(matrix.ty)
+    )
+}
+
+/// Returns the matrix mapping the `uv` polygon of a bitmap onto its `xy` polygon.
+///
+/// The matrix is solved from the first triangle of the polygon whose corners are
+/// not in line in the texture.
+fn texture_matrix(bitmap: &ShapeBitmap) -> Option<Matrix> {
+    let (xy, uv) = (&bitmap.xy, &bitmap.uv);
+    if xy.len() < 3 || uv.len() != xy.len() {
+        return None;
+    }
+
+    for i in 1..xy.len() - 1 {
+        let (du1, dv1) = (uv[i].0 - uv[0].0, uv[i].1 - uv[0].1);
+        let (du2, dv2) = (uv[i + 1].0 - uv[0].0, uv[i + 1].1 - uv[0].1);
+        let det = du1 * dv2 - du2 * dv1;
+        if det.abs() < f32::EPSILON {
+            continue;
+        }
+
+        let (dx1, dy1) = (xy[i].0 - xy[0].0, xy[i].1 - xy[0].1);
+        let (dx2, dy2) = (xy[i + 1].0 - xy[0].0, xy[i + 1].1 - xy[0].1);
+        let a = (dx1 * dv2 - dx2 * dv1) / det;
+        let b = (dy1 * dv2 - dy2 * dv1) / det;
+        let c = (dx2 * du1 - dx1 * du2) / det;
+        let d = (dy2 * du1 - dy1 * du2) / det;
+
+        return Some(Matrix {
+            a,
+            b,
+            c,
+            d,
+            tx: xy[0].0 - a * uv[0].0 - c * uv[0].1,
+            ty: xy[0].1 - b * uv[0].0 - d * uv[0].1,
+        });
+    }
+
+    None
+}
+
+/// Returns the names of the atlases saved by [`process_sc`] for a `_tex.sc` file.
+///
+/// The first texture is saved as `<file name>.png`, and a `_` is appended to the
+/// name for every following texture.
+///
+/// [`process_sc`]: fn.process_sc.html
+///
+/// ## Arguments
+///
+/// * `tex_name`: Name of the `_tex.sc` file without extension, such as `ui_tex`.
+/// * `count`: Number of textures in the file.
+pub fn atlas_names(tex_name: &str, count: usize) -> Vec<String> {
+    (0..count)
+        .map(|i| format!("{}{}.png", tex_name, "_".repeat(i)))
+        .collect()
+}
+
+/// Writes the elements of an `SVG` document.
+struct SvgWriter<'a> {
+    objects: &'a ScObjects,
+    atlases: &'a [String],
+    /// Contents of the `<defs>` element.
+    defs: String,
+    /// Ids of the shapes defined in `defs`.
+    shapes: HashSet<u16>,
+    /// Ids of the color filters defined in `defs`.
+    filters: HashMap<ColorTransform, usize>,
+}
+
+impl<'a> SvgWriter<'a> {
+    fn new(objects: &'a ScObjects, atlases: &'a [String]) -> Self {
+        Self {
+            objects,
+            atlases,
+            defs: String::new(),
+            shapes: HashSet::new(),
+            filters: HashMap::new(),
+        }
+    }
+
+    /// Defines a shape as a group in `defs`, so it can be drawn with `<use>`.
+    fn define_shape(&mut self, shape: &Shape) {
+        if !self.shapes.insert(shape.id) {
+            return;
+        }
+
+        let mut group = format!(r#"<g id="shape_{}">"#, shape.id);
+        for (i, bitmap) in shape.bitmaps.iter().enumerate() {
+            let index = bitmap.texture_index as usize;
+            let (atlas, texture) = match (self.atlases.get(index), self.objects.textures.get(index))
+            {
+                (Some(a), Some(t)) => (a, t),
+                _ => continue,
+            };
+            let matrix = match texture_matrix(bitmap) {
+                Some(m) => m,
+                None => continue,
+            };
+
+            let clip_id = format!("shape_{}_{}", shape.id, i);
+            let _ = write!(
+                self.defs,
+                r#"<clipPath id="{}"><polygon points="{}"/></clipPath>"#,
+                clip_id,
+                points(&bitmap.uv)
+            );
+            let _ = write!(
+                group,
+                r#"<image xlink:href="{}" width="{}" height="{}"{} clip-path="url(#{})"/>"#,
+                escape(atlas),
+                texture.width,
+                texture.height,
+                transform(&matrix),
+                clip_id
+            );
+        }
+        group.push_str("</g>");
+
+        self.defs.push_str(&group);
+    }
+
+    /// Returns the attributes applying a color transformation.
+    ///
+    /// Transformations of alpha only are applied with `opacity`. Others are defined
+    /// as a filter in `defs`.
+    fn color_attributes(&mut self, color: &ColorTransform) -> String {
+        if *color == ColorTransform::IDENTITY {
+            return String::new();
+        }
+        let alpha_only = ColorTransform {
+            a_mul: 255,
+            ..*color
+        } == ColorTransform::IDENTITY;
+        if alpha_only {
+            return format!(r#" opacity="{}""#, num(color.a_mul as f32 / 255.0));
+        }
+
+        let count = self.filters.len();
+        let id = *self.filters.entry(*color).or_insert(count);
+        if id == count {
+            let channel = |mul: u8| num(mul as f32 / 255.0);
+            let _ = write!(
+                self.defs,
+                concat!(
+                    r#"<filter id="color_{}" color-interpolation-filters="sRGB">"#,
+                    r#"<feColorMatrix type="matrix" "#,
+                    r#"values="{} 0 0 0 {} 0 {} 0 0 {} 0 0 {} 0 {} 0 0 0 {} 0"/>"#,
+                    "</filter>"
+                ),
+                id,
+                channel(color.r_mul),
+                channel(color.r_add),
+                channel(color.g_mul),
+                channel(color.g_add),
+                channel(color.b_mul),
+                channel(color.b_add),
+                channel(color.a_mul)
+            );
+        }
+        format!(r#" filter="url(#color_{})""#, id)
+    }
+
+    /// Writes a text field as a `<text>` element centered in its bounds.
+    fn write_text_field(&self, out: &mut String, field: &TextField, attributes: &str) {
+        let text = match field.text.as_deref().filter(|t| !t.is_empty()) {
+            Some(t) => t,
+            None => return,
+        };
+
+        let (left, top, right, bottom) = field.bounds;
+        let (x, anchor) = match align_name(field.align) {
+            "right" => (right as f32, "end"),
+            "center" => ((left as i32 + right as i32) as f32 / 2.0, "middle"),
+            _ => (left as f32, "start"),
+        };
+        let [r, g, b, a] = to_rgba(field.color);
+
+        let _ = write!(
+            out,
+            concat!(
+                r#"<text{} x="{}" y="{}" text-anchor="{}" dominant-baseline="central" "#,
+                r##"font-size="{}" fill="#{:02x}{:02x}{:02x}""##
+            ),
+            attributes,
+            num(x),
+            num((top as i32 + bottom as i32) as f32 / 2.0),
+            anchor,
+            field.font_size,
+            r,
+            g,
+            b
+        );
+        if a != 255 {
+            let _ = write!(out, r#" fill-opacity="{}""#, num(a as f32 / 255.0));
+        }
+        if let Some(font) = &field.font_name {
+            let _ = write!(out, r#" font-family="{}""#, escape(font));
+        }
+        if field.bold {
+            out.push_str(r#" font-weight="bold""#);
+        }
+        if field.italic {
+            out.push_str(r#" font-style="italic""#);
+        }
+        if let (true, Some(color)) = (field.outline, field.outline_color) {
+            let [r, g, b, _] = to_rgba(color);
+            let _ = write!(
+                out,
+                r##" stroke="#{:02x}{:02x}{:02x}" stroke-width="2" paint-order="stroke""##,
+                r, g, b
+            );
+        }
+        let _ = write!(out, ">{}</text>", escape(text));
+    }
+
+    /// Writes the object `id` in the given frame, with the given attributes.
+    ///
+    /// Shapes are drawn with `<use>`, text fields with `<text>` and movie clips
+    /// as groups of their children.
+    fn write_object(
+        &mut self,
+        out: &mut String,
+        id: u16,
+        attributes: &str,
+        frame: usize,
+        depth: usize,
+    ) {
+        let objects = self.objects;
+        if depth > MAX_DEPTH {
+            return;
+        }
+
+        if let Some(shape) = objects.shape(id) {
+            self.define_shape(shape);
+            let _ = write!(out, r##"<use xlink:href="#shape_{}"{}/>"##, id, attributes);
+        } else if let Some(field) = objects.text_field(id) {
+            self.write_text_field(out, field, attributes);
+        } else if let Some(clip) = objects.movie_clip(id) {
+            let _ = write!(out, "<g{}>", attributes);
+            self.write_frame(out, clip, frame, depth);
+            out.push_str("</g>");
+        }
+    }
+
+    /// Writes the children of a movie clip drawn in the given frame.
+    fn write_frame(&mut self, out: &mut String, clip: &MovieClip, frame: usize, depth: usize) {
+        for (child, matrix, color) in self.objects.frame_children(clip, frame) {
+            let mut attributes = transform(matrix);
+            attributes.push_str(&self.color_attributes(color));
+            if let Some(name) = &child.name {
+                let _ = write!(attributes, r#" class="{}""#, escape(name));
+            }
+            self.write_object(out, child.id, &attributes, frame, depth + 1);
+        }
+    }
+
+    /// Returns the document with the given body and bounds.
+    fn finish(self, body: &str, (min_x, min_y, max_x, max_y): Bounds) -> String {
+        let (width, height) = ((max_x - min_x).max(1.0), (max_y - min_y).max(1.0));
+        format!(
+            concat!(
+                r#"<?xml version="1.0" encoding="UTF-8"?>"#,
+                "\n",
+                r#"<svg xmlns="http://www.w3.org/2000/svg" "#,
+                r#"xmlns:xlink="http://www.w3.org/1999/xlink" version="1.1" "#,
+                r#"width="{}" height="{}" viewBox="{} {} {} {}">"#,
+                "\n<defs>{}</defs>\n{}</svg>\n"
+            ),
+            num(width),
+            num(height),
+            num(min_x),
+            num(min_y),
+            num(width),
+            num(height),
+            self.defs,
+            body
+        )
+    }
+}
+
+/// Exports a shape as an `SVG` document.
+///
+/// `atlases` are the file names of the textures, in the order of the textures of
+/// the `.sc` file, as returned by [`atlas_names`]. Bitmaps of textures without an
+/// atlas are left out.
+///
+/// `None` is returned if the shape has no bitmaps.
+///
+/// [`atlas_names`]: fn.atlas_names.html
+///
+/// ## Arguments
+///
+/// * `objects`: Objects of the `.sc` file.
+/// * `shape`: The shape to export.
+/// * `atlases`: File names of the texture atlases, relative to the document.
+pub fn shape_to_svg(objects: &ScObjects, shape: &Shape, atlases: &[String]) -> Option<String> {
+    let bounds = shape_bounds(shape, &Matrix::IDENTITY)?;
+
+    let mut writer = SvgWriter::new(objects, atlases);
+    let mut body = String::new();
+    writer.write_object(&mut body, shape.id, "", 0, 0);
+    body.push('\n');
+
+    Some(writer.finish(&body, bounds))
+}
+
+/// Exports a movie clip as an `SVG` document.
+///
+/// Every frame is a group named `frame_<index>` holding the children drawn in that
+/// frame. Frames after the first are hidden, so each frame can be shown and edited
+/// on its own. Text fields are written as `<text>` in the font of the field.
+/// `atlases` are laid out as described in [`shape_to_svg`].
+///
+/// `None` is returned if the movie clip draws nothing.
+///
+/// [`shape_to_svg`]: fn.shape_to_svg.html
+///
+/// ## Arguments
+///
+/// * `objects`: Objects of the `.sc` file.
+/// * `clip`: The movie clip to export.
+/// * `atlases`: File names of the texture atlases, relative to the document.
+/// * `all_frames`: Whether all frames are exported or only the first one.
+pub fn movie_clip_to_svg(
+    objects: &ScObjects,
+    clip: &MovieClip,
+    atlases: &[String],
+    all_frames: bool,
+) -> Option<String> {
+    let frame_count = if all_frames { clip.frames.len() } else { 1 };
+    let renderer = Renderer {
+        objects,
+        textures: &[],
+    };
+    let bounds = (0..frame_count)
+        .map(|frame| renderer.bounds(clip.id, &Matrix::IDENTITY, frame))
+        .fold(None, union_bounds)?;
+
+    let mut writer = SvgWriter::new(objects, atlases);
+    let mut body = String::new();
+    for (index, frame) in clip.frames.iter().take(frame_count).enumerate() {
+        let _ = write!(body, r#"<g id="frame_{}""#, index);
+        if index > 0 {
+            body.push_str(r#" display="none""#);
+        }
+        body.push('>');
+        if let Some(label) = &frame.label {
+            let _ = write!(body, "<title>{}</title>", escape(label));
+        }
+        writer.write_frame(&mut body, clip, index, 0);
+        body.push_str("</g>\n");
+    }
+
+    Some(writer.finish(&body, bounds))
+}
+
+/// Exports all shapes and exported movie clips of an object file as `SVG` documents.
+///
+/// See [`shape_to_svg`] and [`movie_clip_to_svg`] for the layout of `atlases`
+/// and the documents.
+///
+/// [`shape_to_svg`]: fn.shape_to_svg.html
+/// [`movie_clip_to_svg`]: fn.movie_clip_to_svg.html
+///
+/// ## Arguments
+///
+/// * `objects`: Objects of the `.sc` file.
+/// * `atlases`: File names of the texture atlases, relative to the documents.
+/// * `all_frames`: Whether all frames of movie clips are exported or only the first one.
+pub fn export_svgs(objects: &ScObjects, atlases: &[String], all_frames: bool) -> Vec<SvgDocument> {
+    let shapes = objects.shapes.iter().filter_map(|shape| {
+        Some(SvgDocument {
+            id: shape.id,
+            name: objects.export_name(shape.id).map(str::to_owned),
+            svg: shape_to_svg(objects, shape, atlases)?,
+        })
+    });
+    let clips = objects.exports.iter().filter_map(|export| {
+        let clip = objects.movie_clip(export.id)?;
+        Some(SvgDocument {
+            id: clip.id,
+            name: Some(export.name.clone()),
+            svg: movie_clip_to_svg(objects, clip, atlases, all_frames)?,
+        })
+    });
+
+    shapes.chain(clips).collect()
+}
+
+/// Processes a `.sc` object file into `SVG` documents.
+///
+/// Every shape and exported movie clip is saved as an `SVG` document in a directory
+/// named after the file inside the output directory (`out_dir`). Documents are named
+/// as sprites are by [`process_sprites`], so that shapes and movie clips sharing an
+/// export name are kept apart. The documents refer
+/// to the atlases which [`process_sc`] saves in the output directory for the companion
+/// `_tex.sc` file, so the companion file should be extracted to the same directory.
+///
+/// If saving the documents is unsuccessful, the error is raised wrapped
+/// in `Error::File`.
+///
+/// [`process_sc`]: fn.process_sc.html
+/// [`process_sprites`]: fn.process_sprites.html
+/// [`decode_objects`]: objects/fn.decode_objects.html
+///
+/// ## Arguments
+///
+/// * `objects`: Objects of the `.sc` file, as returned by [`decode_objects`].
+/// * `path`: Path to the `.sc` file. It is used to get file name.
+/// * `out_dir`: Directory to store the documents.
+/// * `all_frames`: Whether all frames of movie clips are exported or only the first one.
+/// * `parallelize`: Whether files are processed in parallel or not.
+pub fn process_svgs(
+    objects: &ScObjects,
+    path: &Path,
+    out_dir: &Path,
+    all_frames: bool,
+    parallelize: bool,
+) -> Result<()> {
+    let file_name = path.file_stem().unwrap().to_str().unwrap();
+
+    if !parallelize {
+        println!(
+            "\nExporting SVG documents of {}...",
+            path.file_name().unwrap().to_str().unwrap().green().bold()
+        );
+    }
+
+    let atlases: Vec<_> = atlas_names(&format!("{}_tex", file_name), objects.textures.len())
+        .into_iter()
+        .map(|name| format!("../{}", name))
+        .collect();
+    let documents = export_svgs(objects, &atlases, all_frames);
+
+    let svg_dir = out_dir.join(file_name);
+    fs::create_dir_all(&svg_dir).map_err(|e| Error::from(e).in_file(&svg_dir))?;
+
+    for document in &documents {
+        let name = object_file_name(document.name.as_deref(), "shape", document.id);
+        let out_path = svg_dir.join(format!("{}.svg", name));
+        fs::write(&out_path, &document.svg).map_err(|e| Error::from(e).in_file(&out_path))?;
+    }
+
+    println!(
+        "{} SVG document(s) exported from {}",
+        documents.len().to_string().cyan().bold(),
+        file_name.green()
+    );
+
+    Ok(())
+}
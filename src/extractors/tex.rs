@@ -1,107 +1,512 @@
 use crate::{
-    errors::{DecompressionError, UnknownPixel},
+    detect::is_pvr,
+    errors::{Error, Result},
+    extractors::{ktx::decode_ktx, objects::read_tag, pvr::decode_pvr},
+    header::ScHeader,
     utils::{decompress, Reader},
 };
 use colored::Colorize;
 use image::{Rgba, RgbaImage};
-use std::path::Path;
+use std::{
+    convert::TryFrom,
+    fs,
+    io::Cursor,
+    path::{Component, Path},
+};
+
+/// Types of texture blocks storing their pixels directly.
+const PIXEL_TEXTURE_TAGS: [u8; 8] = [1, 16, 19, 24, 27, 28, 29, 34];
 
-/// Reads some data from the stream and returns appropriate pixel data.
+/// Types of texture blocks storing their pixels by tiles. See [`read_image`].
 ///
-/// The bitwise transformations depend on the type of the pixel. One of the following
-/// types is valid: `0, 1, 2, 3, 4, 6, 10`.
+/// [`read_image`]: fn.read_image.html
+const TILED_TEXTURE_TAGS: [u8; 3] = [27, 28, 29];
+
+/// Types of texture blocks storing `KTX` images. See [`read_ktx_texture`].
 ///
-/// If `pixel_type` is not one of the above, `UnknownPixel` is raised. Otherwise, an array
-/// of four `u8`s is returned, wrapped around by `Ok`.
+/// [`read_ktx_texture`]: fn.read_ktx_texture.html
+const KTX_TEXTURE_TAGS: [u8; 2] = [45, 47];
+
+/// Pixel format of a texture stored directly in a `_tex.sc` block, given by its sub-type.
 ///
-/// ## Arguments
+/// Channels with less than 8 bits are expanded by repeating their high bits, so
+/// that full intensity stays full intensity (`0xF` becomes `0xFF`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PixelFormat {
+    /// Sub-types `0` and `1`: 8 bits per channel, in `RGBA` order.
+    Rgba8888,
+    /// Sub-types `2` and `9`: 4 bits per channel, red in the high bits of a 16-bit value.
+    Rgba4444,
+    /// Sub-type `3`: 5 bits for red, green and blue, and 1 bit for alpha in the low bit.
+    Rgba5551,
+    /// Sub-type `4`: 5 bits for red and blue and 6 bits for green. Pixels are opaque.
+    Rgb565,
+    /// Sub-type `5` with one byte per pixel: 4 bits of luminance and 4 bits of alpha.
+    La44,
+    /// Sub-type `5` with two bytes per pixel: 5 bits for red, green and blue. Pixels
+    /// are opaque.
+    Rgb555,
+    /// Sub-type `6`: 8 bits of luminance in the high byte and 8 bits of alpha.
+    La88,
+    /// Sub-type `10`: 8 bits of luminance. Pixels are opaque.
+    L8,
+}
+
+impl PixelFormat {
+    /// Returns the pixel format of a texture sub-type, or `None` for unknown sub-types.
+    ///
+    /// Sub-type `5` is used for both `LA44` and `RGB555` pixels. They are told apart by
+    /// the size of the texture's pixel data: `RGB555` is chosen if there are at least
+    /// two bytes per pixel.
+    ///
+    /// ## Arguments
+    ///
+    /// * `sub_type`: Sub-type of the texture.
+    /// * `bytes_per_pixel`: Size of the texture's pixel data divided by its number of pixels.
+    pub fn from_sub_type(sub_type: u8, bytes_per_pixel: usize) -> Option<Self> {
+        Some(match sub_type {
+            0 | 1 => PixelFormat::Rgba8888,
+            2 | 9 => PixelFormat::Rgba4444,
+            3 => PixelFormat::Rgba5551,
+            4 => PixelFormat::Rgb565,
+            5 if bytes_per_pixel >= 2 => PixelFormat::Rgb555,
+            5 => PixelFormat::La44,
+            6 => PixelFormat::La88,
+            10 => PixelFormat::L8,
+            _ => return None,
+        })
+    }
+
+    /// Returns the size of a pixel in bytes.
+    pub fn size(&self) -> usize {
+        match self {
+            PixelFormat::Rgba8888 => 4,
+            PixelFormat::La44 | PixelFormat::L8 => 1,
+            _ => 2,
+        }
+    }
+
+    /// Decodes a single pixel to `RGBA`.
+    ///
+    /// `None` is returned if `bytes` holds less than [`size`] bytes.
+    ///
+    /// [`size`]: #method.size
+    ///
+    /// ## Arguments
+    ///
+    /// * `bytes`: Bytes of the pixel. Multi-byte values are little-endian.
+    pub fn decode(&self, bytes: &[u8]) -> Option<[u8; 4]> {
+        let bytes = bytes.get(..self.size())?;
+        let short = || u16::from_le_bytes([bytes[0], bytes[1]]);
+        Some(match self {
+            PixelFormat::Rgba8888 => [bytes[0], bytes[1], bytes[2], bytes[3]],
+            PixelFormat::Rgba4444 => {
+                let p = short();
+                [
+                    expand(p >> 12, 4),
+                    expand(p >> 8, 4),
+                    expand(p >> 4, 4),
+                    expand(p, 4),
+                ]
+            }
+            PixelFormat::Rgba5551 => {
+                let p = short();
+                [
+                    expand(p >> 11, 5),
+                    expand(p >> 6, 5),
+                    expand(p >> 1, 5),
+                    expand(p, 1),
+                ]
+            }
+            PixelFormat::Rgb565 => {
+                let p = short();
+                [expand(p >> 11, 5), expand(p >> 5, 6), expand(p, 5), 255]
+            }
+            PixelFormat::La44 => {
+                let l = expand(bytes[0] as u16 >> 4, 4);
+                [l, l, l, expand(bytes[0] as u16, 4)]
+            }
+            PixelFormat::Rgb555 => {
+                let p = short();
+                [expand(p >> 10, 5), expand(p >> 5, 5), expand(p, 5), 255]
+            }
+            PixelFormat::La88 => {
+                let p = short();
+                let l = (p >> 8) as u8;
+                [l, l, l, p as u8]
+            }
+            PixelFormat::L8 => [bytes[0], bytes[0], bytes[0], 255],
+        })
+    }
+}
+
+/// Expands the low `bits` bits of `value` to 8 bits by repeating them from the highest.
+fn expand(value: u16, bits: u32) -> u8 {
+    let value = value & ((1 << bits) - 1);
+    let mut result = 0;
+    let mut filled = 0;
+    while filled < 8 {
+        result = (result << bits) | value;
+        filled += bits;
+    }
+    (result >> (filled - 8)) as u8
+}
+
+/// Size of the tiles of tiled textures, in pixels.
+const TILE_SIZE: u32 = 32;
+
+/// Returns the positions of the pixels of a tiled image, in the order they are stored.
+///
+/// Tiles are stored row by row, and so are the pixels of each tile. Tiles at the right
+/// and bottom edges are cut to the size of the image.
+fn tiled_positions(width: u32, height: u32) -> impl Iterator<Item = (u32, u32)> {
+    (0..height.div_ceil(TILE_SIZE)).flat_map(move |tile_y| {
+        (0..width.div_ceil(TILE_SIZE)).flat_map(move |tile_x| {
+            let rows = tile_y * TILE_SIZE..((tile_y + 1) * TILE_SIZE).min(height);
+            rows.flat_map(move |y| {
+                let columns = tile_x * TILE_SIZE..((tile_x + 1) * TILE_SIZE).min(width);
+                columns.map(move |x| (x, y))
+            })
+        })
+    })
+}
+
+/// A single texture decoded from `_tex.sc` data.
+///
+/// `file_type` and `sub_type` are the raw values stored in the texture's block
+/// header. `sub_type` determines the pixel format of the texture.
+pub struct Texture {
+    /// Type of the texture block.
+    pub file_type: u8,
+    /// Pixel format of the texture.
+    pub sub_type: u8,
+    /// Width of the texture in pixels.
+    pub width: u16,
+    /// Height of the texture in pixels.
+    pub height: u16,
+    /// Decoded texture.
+    pub image: RgbaImage,
+    /// Mipmap levels stored after the texture, from the largest one. Each level is
+    /// half the size of the previous one.
+    pub mipmaps: Vec<RgbaImage>,
+}
+
+/// Reads an image of `width` by `height` pixels in the given format.
 ///
-/// * `reader`: `Reader` representing the data stream.
-/// * `pixel_type`: The type of pixel. For `_tex.sc` data, it is the image sub-type.
-fn convert_pixel(reader: &mut Reader, pixel_type: u8) -> Result<[u8; 4], UnknownPixel> {
-    match pixel_type {
-        // RGB8888
-        0 | 1 => {
-            let pixel = reader.read(4);
-            Ok([pixel[0], pixel[1], pixel[2], pixel[3]])
+/// Pixels of tiled images are stored by tiles of 32x32 pixels, as in blocks of
+/// type `27`, `28` and `29`. If `reader` holds less than the image's pixel data,
+/// `Error::UnexpectedEof` is raised before the image is allocated.
+fn read_image(
+    reader: &mut Reader,
+    format: PixelFormat,
+    width: u16,
+    height: u16,
+    tiled: bool,
+) -> Result<RgbaImage> {
+    // Sizes which overflow are larger than any data, and reading fails.
+    let size = (width as usize * height as usize).saturating_mul(format.size());
+    let data = reader.read(size)?;
+
+    let mut img = RgbaImage::new(width as u32, height as u32);
+    let pixels = data
+        .chunks_exact(format.size())
+        .filter_map(|p| format.decode(p));
+    if tiled {
+        for ((x, y), pixel) in tiled_positions(width as u32, height as u32).zip(pixels) {
+            img.put_pixel(x, y, Rgba(pixel));
         }
-        // RGB4444
-        2 => {
-            let pixel = reader.read_uint16();
-            Ok([
-                (((pixel >> 12) & 0xF) << 4) as u8,
-                (((pixel >> 8) & 0xF) << 4) as u8,
-                (((pixel >> 4) & 0xF) << 4) as u8,
-                ((pixel & 0xF) << 4) as u8,
-            ])
+    } else {
+        for (dest, pixel) in img.pixels_mut().zip(pixels) {
+            *dest = Rgba(pixel);
         }
-        // RGBA5551
-        3 => {
-            let pixel = reader.read_uint16();
-            Ok([
-                (((pixel >> 11) & 0x1F) << 3) as u8,
-                (((pixel >> 6) & 0x1F) << 3) as u8,
-                (((pixel >> 1) & 0x1F) << 3) as u8,
-                ((pixel & 0xFF) << 7) as u8,
-            ])
+    }
+
+    Ok(img)
+}
+
+/// Reads a single texture from the block's data, with its mipmap levels if any.
+///
+/// ## Arguments
+///
+/// * `reader`: `Reader` limited to the data of the texture block.
+/// * `file_type`: The type of the texture block.
+fn read_texture(reader: &mut Reader, file_type: u8) -> Result<Texture> {
+    let sub_type = reader.read_byte()?;
+    let width = reader.read_uint16()?;
+    let height = reader.read_uint16()?;
+
+    let pixel_count = width as usize * height as usize;
+    let bytes_per_pixel = reader.len() / pixel_count.max(1);
+    let format =
+        PixelFormat::from_sub_type(sub_type, bytes_per_pixel).ok_or(Error::UnknownPixel {
+            pixel_type: sub_type,
+            offset: reader.tell(),
+        })?;
+
+    let tiled = TILED_TEXTURE_TAGS.contains(&file_type);
+    let image = read_image(reader, format, width, height, tiled)?;
+
+    // Levels are read as long as the block holds them.
+    let mut mipmaps = Vec::new();
+    let (mut level_width, mut level_height) = (width, height);
+    while level_width > 1 || level_height > 1 {
+        level_width = (level_width / 2).max(1);
+        level_height = (level_height / 2).max(1);
+        if reader.len() < level_width as usize * level_height as usize * format.size() {
+            break;
         }
-        // RGB565
-        4 => {
-            let pixel = reader.read_uint16();
-            Ok([
-                (((pixel >> 11) & 0x1F) << 3) as u8,
-                (((pixel >> 5) & 0x3F) << 2) as u8,
-                ((pixel & 0x1F) << 3) as u8,
-                // Alpha channel must always be 255 for type 4.
-                255,
-            ])
+        mipmaps.push(read_image(
+            reader,
+            format,
+            level_width,
+            level_height,
+            tiled,
+        )?);
+    }
+
+    Ok(Texture {
+        file_type,
+        sub_type,
+        width,
+        height,
+        image,
+        mipmaps,
+    })
+}
+
+/// Reads a single texture stored as a `KTX` image from the block's data.
+///
+/// Blocks of type `45` start with the length of the embedded image and blocks
+/// of type `47` with the name of the file storing the image. The pixel type and
+/// size of the texture follow, as in other blocks, and then the embedded image.
+///
+/// External files are loaded from `dir`. Without `dir`, `Error::Unsupported` is
+/// raised for them. File names must be relative and stay within `dir`, otherwise
+/// `Error::InvalidData` is raised.
+///
+/// ## Arguments
+///
+/// * `reader`: `Reader` limited to the data of the texture block.
+/// * `file_type`: The type of the texture block.
+/// * `dir`: Directory of the `_tex.sc` file.
+fn read_ktx_texture(reader: &mut Reader, file_type: u8, dir: Option<&Path>) -> Result<Texture> {
+    let (ktx_size, file_name) = match file_type {
+        45 => (reader.read_uint32()? as usize, None),
+        _ => (0, reader.read_ascii()?),
+    };
+    let sub_type = reader.read_byte()?;
+    reader.read_uint16()?;
+    reader.read_uint16()?;
+
+    let image = match (file_name, dir) {
+        (None, _) => decode_ktx(&reader.read(ktx_size)?)?,
+        (Some(name), Some(dir)) => {
+            let is_relative = Path::new(&name)
+                .components()
+                .all(|c| matches!(c, Component::Normal(_) | Component::CurDir));
+            if name.is_empty() || !is_relative {
+                return Err(Error::InvalidData(format!(
+                    "external texture file name `{}`",
+                    name
+                )));
+            }
+            let path = dir.join(&name);
+            // Files are named relative to the game's assets, e.g. `sc/ui_tex.zktx`.
+            let path = if path.exists() {
+                path
+            } else {
+                dir.join(Path::new(&name).file_name().unwrap_or_default())
+            };
+            let data = fs::read(&path).map_err(|e| Error::from(e).in_file(&path))?;
+            decode_image(&data).map_err(|e| e.in_file(&path))?
         }
-        // LA88
-        6 => {
-            let pixel = reader.read_uint16();
-            Ok([
-                (pixel >> 8) as u8,
-                (pixel >> 8) as u8,
-                (pixel >> 8) as u8,
-                (pixel & 0xFF) as u8,
-            ])
+        (Some(name), None) => {
+            return Err(Error::Unsupported(format!(
+                "texture stored in external file `{}`",
+                name
+            )))
         }
-        10 => {
-            let pixel = reader.read_byte();
-            Ok([pixel; 4])
+    };
+
+    let (width, height) = match (u16::try_from(image.width()), u16::try_from(image.height())) {
+        (Ok(w), Ok(h)) => (w, h),
+        _ => {
+            return Err(Error::InvalidData(format!(
+                "texture of {}x{} pixels is too large",
+                image.width(),
+                image.height()
+            )))
         }
-        _ => Err(UnknownPixel(format!(
-            "Unknown pixel type ({}).",
-            pixel_type
-        ))),
-    }
+    };
+
+    Ok(Texture {
+        file_type,
+        sub_type,
+        width,
+        height,
+        image,
+        mipmaps: Vec::new(),
+    })
+}
+
+/// Decodes the texture blocks of `_tex.sc` data, loading external textures from `dir`.
+pub(crate) fn decode_tex_data(data: &[u8], dir: Option<&Path>) -> Result<Vec<Result<Texture>>> {
+    let header = ScHeader::parse(data)?;
+    let decompressed = decompress(header.payload(data))?;
+
+    Ok(parse_tex_blocks(decompressed.into_inner(), dir))
 }
 
-/// Adjusts some pixels.
-fn adjust_pixels(img: &mut RgbaImage, pixels: Vec<[u8; 4]>, height: u16, width: u16) {
-    let mut i = 0;
-    let block_size = 32;
-    let h_limit = (height as f64 / block_size as f64).ceil() as u32;
-    let w_limit = (width as f64 / block_size as f64).ceil() as u32;
-
-    for _h in 0..h_limit {
-        for _w in 0..w_limit {
-            let mut h = _h * block_size;
-            while h != (_h + 1) * block_size && h < height as u32 {
-                let mut w = _w * block_size;
-                while w != (_w + 1) * block_size && w < width as u32 {
-                    img.put_pixel(
-                        w,
-                        h,
-                        Rgba([pixels[i][0], pixels[i][1], pixels[i][2], pixels[i][3]]),
-                    );
-                    i += 1;
-                    w += 1;
-                }
-                h += 1;
+/// Decodes the texture blocks of decompressed `_tex.sc` data into per-block results.
+///
+/// Works as [`decode_tex_blocks`] and [`decode_tex_blocks_in`] on data which is
+/// already decompressed, such as the data returned by [`detect_file`]. Textures
+/// stored in external files are loaded from `dir`, if given.
+///
+/// [`decode_tex_blocks`]: fn.decode_tex_blocks.html
+/// [`decode_tex_blocks_in`]: fn.decode_tex_blocks_in.html
+/// [`detect_file`]: fn.detect_file.html
+///
+/// ## Arguments
+///
+/// * `decompressed`: Decompressed `_tex.sc` data, without the header.
+/// * `dir`: Directory containing the `_tex.sc` file.
+pub fn parse_tex_blocks(decompressed: Vec<u8>, dir: Option<&Path>) -> Vec<Result<Texture>> {
+    let mut reader = Reader::new(Cursor::new(decompressed));
+
+    let mut textures = Vec::new();
+
+    // Each block is read from a reader limited to its declared size, so that
+    // the next block is found whatever was read from this one.
+    loop {
+        let (file_type, mut block) = match read_tag(&mut reader) {
+            Ok(Some(tag)) => tag,
+            Ok(None) => break,
+            Err(e) => {
+                textures.push(Err(e));
+                break;
             }
+        };
+
+        if KTX_TEXTURE_TAGS.contains(&file_type) {
+            textures.push(read_ktx_texture(&mut block, file_type, dir));
+        } else if PIXEL_TEXTURE_TAGS.contains(&file_type) {
+            textures.push(read_texture(&mut block, file_type));
         }
     }
+
+    textures
+}
+
+/// Decodes compressed, raw `_tex.sc` file data into per-block results.
+///
+/// Unlike [`decode_tex`], damaged texture blocks are not skipped. An `Err` is
+/// returned in place of each texture that could not be decoded, so that the
+/// remaining textures can still be used. Blocks are decoded within their declared
+/// size, so a damaged texture doesn't affect the following ones.
+///
+/// If the header is invalid, `Error::InvalidHeader` or `Error::UnexpectedEof` is
+/// raised. If decompression is unsuccessful, `Error::Decompression` is raised.
+/// Textures stored in external files are returned as `Err`. See [`decode_tex_blocks_in`]
+/// to load them.
+///
+/// [`decode_tex`]: fn.decode_tex.html
+/// [`decode_tex_blocks_in`]: fn.decode_tex_blocks_in.html
+///
+/// ## Arguments
+///
+/// * `data`: Raw `_tex.sc` file data.
+pub fn decode_tex_blocks(data: &[u8]) -> Result<Vec<Result<Texture>>> {
+    decode_tex_data(data, None)
+}
+
+/// Decodes compressed, raw `_tex.sc` file data into per-block results, loading
+/// textures stored in external `.ktx` or `.zktx` files from `dir`.
+///
+/// Newer `_tex.sc` files name the files storing their textures instead of storing
+/// the pixels. Errors are raised as in [`decode_tex_blocks`]. Textures whose files
+/// can't be read are returned as `Err`.
+///
+/// [`decode_tex_blocks`]: fn.decode_tex_blocks.html
+///
+/// ## Arguments
+///
+/// * `data`: Raw `_tex.sc` file data.
+/// * `dir`: Directory containing the `_tex.sc` file.
+pub fn decode_tex_blocks_in(data: &[u8], dir: &Path) -> Result<Vec<Result<Texture>>> {
+    decode_tex_data(data, Some(dir))
+}
+
+/// Decodes compressed, raw `_tex.sc` file data into textures.
+///
+/// A single `_tex.sc` file can contain data for multiple sprites. All of the sprites
+/// are decoded and returned in the order they appear in the file. Nothing is written
+/// to the disk.
+///
+/// If the header is invalid, `Error::InvalidHeader` or `Error::UnexpectedEof` is
+/// raised. If decompression is unsuccessful, `Error::Decompression` is raised.
+/// Textures with unknown pixel types or truncated data are skipped. See
+/// [`decode_tex_blocks`] to find out which textures were skipped.
+///
+/// [`decode_tex_blocks`]: fn.decode_tex_blocks.html
+///
+/// ## Arguments
+///
+/// * `data`: Raw `_tex.sc` file data.
+pub fn decode_tex(data: &[u8]) -> Result<Vec<Texture>> {
+    Ok(decode_tex_blocks(data)?
+        .into_iter()
+        .filter_map(|t| t.ok())
+        .collect())
+}
+
+/// Decodes a standalone texture file: a `KTX` image, a `.zktx` image or a `PVR` image.
+///
+/// The type of the image is detected from its contents. See [`decode_ktx`] and
+/// [`decode_pvr`] for the supported texture formats.
+///
+/// [`decode_ktx`]: fn.decode_ktx.html
+/// [`decode_pvr`]: fn.decode_pvr.html
+///
+/// ## Arguments
+///
+/// * `data`: Image data.
+pub fn decode_image(data: &[u8]) -> Result<RgbaImage> {
+    if is_pvr(data) {
+        decode_pvr(data)
+    } else {
+        decode_ktx(data)
+    }
+}
+
+/// Processes a standalone texture file, such as the `.pvr` files of older iOS builds.
+///
+/// The image is decoded with [`decode_image`] and saved in `PNG` format in the output
+/// directory (`out_dir`), named after the file. If decoding or saving the image is
+/// unsuccessful, the error is raised wrapped in `Error::File`.
+///
+/// [`decode_image`]: fn.decode_image.html
+///
+/// ## Arguments
+///
+/// * `data`: Image data.
+/// * `path`: Path to the image file. It is used to get file name.
+/// * `out_dir`: Directory to store the extracted image.
+/// * `_parallelize`: Whether files are processed in parallel or not.
+///   Has no effect on this function.
+pub fn process_image(data: &[u8], path: &Path, out_dir: &Path, _parallelize: bool) -> Result<()> {
+    let image = decode_image(data).map_err(|e| e.in_file(path))?;
+
+    println!(
+        "\nExtracting {} image...",
+        path.file_name().unwrap().to_str().unwrap().green().bold()
+    );
+
+    let file_name = path.file_stem().unwrap().to_str().unwrap();
+    let out_path = out_dir.join(format!("{}.png", file_name));
+    image
+        .save(&out_path)
+        .map_err(|e| Error::from(e).in_file(out_path))
 }
 
 /// Processes compressed, raw `_tex.sc` file data.
@@ -109,12 +514,16 @@ fn adjust_pixels(img: &mut RgbaImage, pixels: Vec<[u8; 4]>, height: u16, width:
 /// If decompressing and pixel conversion is successful, the resultant image
 /// is saved in `PNG` format in the output directory (`out_dir`).
 ///
-/// If decompression is unsuccessful, `DecompressionError` is raised. Pixel
-/// conversion errors are handled in the function itself.
+/// If decoding or saving the images is unsuccessful, the error is raised wrapped
+/// in `Error::File`. Damaged textures are reported on `stdout` and the remaining
+/// textures are still extracted.
 ///
 /// A single `_tex.sc` file can contain data for multiple sprites. All of the sprites
-/// are extracted and saved by this process. `_`s are appended to the file name in cases
-/// of multiple sprites.
+/// are extracted and saved by this process, including those stored in `.ktx` or
+/// `.zktx` files next to the `_tex.sc` file. `_`s are appended to the file name in cases
+/// of multiple sprites. See [`decode_tex`] to get the sprites without saving them.
+///
+/// [`decode_tex`]: fn.decode_tex.html
 ///
 /// ## Arguments
 ///
@@ -122,26 +531,71 @@ fn adjust_pixels(img: &mut RgbaImage, pixels: Vec<[u8; 4]>, height: u16, width:
 /// * `path`: Path to the `_tex.sc` file. It is used to get file name.
 /// * `out_dir`: Directory to store extracted images.
 /// * `parallelize`: Whether files are processed in parallel or not.
-pub fn process_sc(
+pub fn process_sc(data: &[u8], path: &Path, out_dir: &Path, parallelize: bool) -> Result<()> {
+    save_textures(data, path, out_dir, parallelize, false)
+}
+
+/// Processes compressed, raw `_tex.sc` file data, saving mipmap levels too.
+///
+/// Works as [`process_sc`], and also saves the mipmap levels of each texture in
+/// `PNG` format, with `_mip1`, `_mip2` and so on appended to the texture's file name.
+///
+/// [`process_sc`]: fn.process_sc.html
+///
+/// ## Arguments
+///
+/// * `data`: Raw `_tex.sc` file data.
+/// * `path`: Path to the `_tex.sc` file. It is used to get file name.
+/// * `out_dir`: Directory to store extracted images.
+/// * `parallelize`: Whether files are processed in parallel or not.
+pub fn process_sc_with_mipmaps(
     data: &[u8],
     path: &Path,
     out_dir: &Path,
     parallelize: bool,
-) -> Result<(), DecompressionError> {
-    if data.len() < 35 {
-        return Err(DecompressionError("Size of file is too small:".to_string()));
-    }
-
-    let decompressed = match decompress(&data[26..]) {
-        Ok(c) => c,
-        Err(e) => return Err(e),
-    };
+) -> Result<()> {
+    save_textures(data, path, out_dir, parallelize, true)
+}
 
-    let mut reader = Reader::new(decompressed);
+/// Saves the textures of `_tex.sc` data, and their mipmap levels if `mipmaps` is set.
+fn save_textures(
+    data: &[u8],
+    path: &Path,
+    out_dir: &Path,
+    parallelize: bool,
+    mipmaps: bool,
+) -> Result<()> {
+    let dir = path.parent().unwrap_or_else(|| Path::new("."));
+    let blocks = decode_tex_blocks_in(data, dir).map_err(|e| e.in_file(path))?;
 
-    let mut pic_count = 0;
-    let possible_types = [1, 24, 27, 28];
+    save_tex_blocks(blocks, path, out_dir, parallelize, mipmaps)
+}
 
+/// Saves decoded texture blocks of a `_tex.sc` file, as [`process_sc`] does.
+///
+/// Damaged textures are reported on `stdout` and the remaining textures are still
+/// saved. If saving an image is unsuccessful, the error is raised wrapped in
+/// `Error::File`. Mipmap levels are saved as well if `mipmaps` is set, as in
+/// [`process_sc_with_mipmaps`].
+///
+/// [`process_sc`]: fn.process_sc.html
+/// [`process_sc_with_mipmaps`]: fn.process_sc_with_mipmaps.html
+/// [`parse_tex_blocks`]: fn.parse_tex_blocks.html
+///
+/// ## Arguments
+///
+/// * `blocks`: Texture blocks, as returned by [`parse_tex_blocks`].
+/// * `path`: Path to the `_tex.sc` file. It is used to get file name.
+/// * `out_dir`: Directory to store extracted images.
+/// * `parallelize`: Whether files are processed in parallel or not.
+/// * `mipmaps`: Whether mipmap levels are saved.
+pub fn save_tex_blocks(
+    blocks: Vec<Result<Texture>>,
+    path: &Path,
+    out_dir: &Path,
+    parallelize: bool,
+    mipmaps: bool,
+) -> Result<()> {
     let file_name = path.file_stem().unwrap().to_str().unwrap();
 
     if !parallelize {
@@ -151,57 +605,48 @@ pub fn process_sc(
         );
     }
 
-    'main: while reader.len() > 0 {
-        let file_type = reader.read_byte();
-        let file_size = reader.read_uint32();
-
-        if !possible_types.contains(&file_type) {
-            reader.read(file_size as usize);
-            continue;
-        }
-
-        let sub_type = reader.read_byte();
-        let width = reader.read_uint16();
-        let height = reader.read_uint16();
+    let mut pic_count = 0;
+    for (index, block) in blocks.into_iter().enumerate() {
+        let texture = match block {
+            Ok(t) => t,
+            Err(e) => {
+                println!(
+                    "{} {}",
+                    format!("Skipping damaged texture #{} in {}:", index, file_name).red(),
+                    e.to_string().red()
+                );
+                continue;
+            }
+        };
 
         println!(
-            "file_type: {}, file_size: {}, sub_type: {}, width: {}, height: {}",
-            file_type.to_string().cyan().bold(),
-            file_size.to_string().cyan().bold(),
-            sub_type.to_string().cyan().bold(),
-            width.to_string().cyan().bold(),
-            height.to_string().cyan().bold()
+            "file_type: {}, sub_type: {}, width: {}, height: {}",
+            texture.file_type.to_string().cyan().bold(),
+            texture.sub_type.to_string().cyan().bold(),
+            texture.width.to_string().cyan().bold(),
+            texture.height.to_string().cyan().bold()
         );
 
-        let mut pixels = Vec::new();
-        let mut img = RgbaImage::new(width as u32, height as u32);
-        for y in 0..height {
-            for x in 0..width {
-                let [one, two, three, four] = match convert_pixel(&mut reader, sub_type) {
-                    Ok(v) => v,
-                    Err(e) => {
-                        println!("Error: {}", e.0.red());
-                        continue 'main;
-                    }
-                };
-                pixels.push([one, two, three, four]);
-                // img.put_pix
-                img.put_pixel(x as u32, y as u32, Rgba([one, two, three, four]));
-            }
-        }
-
-        if file_type == 27 || file_type == 28 {
-            adjust_pixels(&mut img, pixels, height, width);
-        }
-
         let initial_path = out_dir.join(file_name);
-        let path = format!(
-            "{}{}.png",
+        let base_path = format!(
+            "{}{}",
             initial_path.to_str().unwrap(),
             "_".repeat(pic_count)
         );
-        img.save(path)
-            .unwrap_or_else(|_| panic!("Failed to save image!".red()));
+        let out_path = format!("{}.png", base_path);
+        texture
+            .image
+            .save(&out_path)
+            .map_err(|e| Error::from(e).in_file(out_path))?;
+
+        if mipmaps {
+            for (level, image) in texture.mipmaps.iter().enumerate() {
+                let out_path = format!("{}_mip{}.png", base_path, level + 1);
+                image
+                    .save(&out_path)
+                    .map_err(|e| Error::from(e).in_file(out_path))?;
+            }
+        }
 
         pic_count += 1;
     }
@@ -0,0 +1,431 @@
+//! Export of text field styles and rendering of text field previews.
+
+use crate::{
+    errors::{Error, Result},
+    extractors::{
+        animation::MAX_SIZE,
+        objects::{ScObjects, TextField},
+        sprites::object_file_name,
+    },
+};
+use ab_glyph::{Font, FontArc, Glyph, PxScale, ScaleFont};
+use colored::Colorize;
+use image::RgbaImage;
+use serde::Serialize;
+use std::{fs, io::BufWriter, path::Path};
+
+/// Shear applied to glyphs of italic text.
+const ITALIC_SHEAR: f32 = 0.2;
+
+/// Ubuntu Light, used to render previews when no font is given. It is distributed
+/// under the Ubuntu Font Licence, found next to it in `assets/UFL.txt`.
+const FALLBACK_FONT: &[u8] = include_bytes!("../../assets/Ubuntu-Light.ttf");
+
+/// Bounds of a text field, in pixels.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct TextBounds {
+    pub left: i16,
+    pub top: i16,
+    pub right: i16,
+    pub bottom: i16,
+}
+
+/// Style of a text field, in a form usable outside of the game.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TextStyle {
+    /// Id of the text field.
+    pub id: u16,
+    /// Names given to the text field by the movie clips containing it.
+    pub names: Vec<String>,
+    pub font_name: Option<String>,
+    pub font_size: u8,
+    /// Color of the text as a `#rrggbbaa` hex string.
+    pub color: String,
+    /// Color of the outline as a `#rrggbbaa` hex string, if the text is outlined.
+    pub outline_color: Option<String>,
+    pub bold: bool,
+    pub italic: bool,
+    pub multiline: bool,
+    pub outline: bool,
+    /// One of `left`, `right`, `center` and `justify`.
+    pub align: &'static str,
+    pub bounds: TextBounds,
+    /// Default text of the text field.
+    pub text: Option<String>,
+    pub use_device_font: bool,
+}
+
+/// Converts an `ARGB` color to `RGBA`. Colors without alpha are made opaque.
+pub(crate) fn to_rgba(color: u32) -> [u8; 4] {
+    let [a, r, g, b] = color.to_be_bytes();
+    [r, g, b, if a == 0 { 255 } else { a }]
+}
+
+/// Formats an `ARGB` color as a `#rrggbbaa` hex string.
+fn to_hex_color(color: u32) -> String {
+    let [r, g, b, a] = to_rgba(color);
+    format!("#{:02x}{:02x}{:02x}{:02x}", r, g, b, a)
+}
+
+/// Returns the name of a text field's alignment.
+pub(crate) fn align_name(align: u8) -> &'static str {
+    match align & 3 {
+        1 => "right",
+        2 => "center",
+        3 => "justify",
+        _ => "left",
+    }
+}
+
+impl TextStyle {
+    /// Returns the style of `field`.
+    ///
+    /// ## Arguments
+    ///
+    /// * `field`: The text field.
+    /// * `objects`: Objects of the `.sc` file, used to find the names of the text field.
+    pub fn new(field: &TextField, objects: &ScObjects) -> Self {
+        let mut names: Vec<String> = objects
+            .movie_clips
+            .iter()
+            .flat_map(|c| c.children.iter())
+            .filter(|c| c.id == field.id)
+            .filter_map(|c| c.name.clone())
+            .collect();
+        names.sort();
+        names.dedup();
+
+        let (left, top, right, bottom) = field.bounds;
+
+        Self {
+            id: field.id,
+            names,
+            font_name: field.font_name.clone(),
+            font_size: field.font_size,
+            color: to_hex_color(field.color),
+            outline_color: match (field.outline, field.outline_color) {
+                (true, Some(c)) => Some(to_hex_color(c)),
+                (true, None) => Some("#000000ff".to_owned()),
+                (false, _) => None,
+            },
+            bold: field.bold,
+            italic: field.italic,
+            multiline: field.multiline,
+            outline: field.outline,
+            align: align_name(field.align),
+            bounds: TextBounds {
+                left,
+                top,
+                right,
+                bottom,
+            },
+            text: field.text.clone(),
+            use_device_font: field.use_device_font,
+        }
+    }
+}
+
+/// Returns the styles of all text fields of an object file.
+///
+/// ## Arguments
+///
+/// * `objects`: Objects of the `.sc` file.
+pub fn text_styles(objects: &ScObjects) -> Vec<TextStyle> {
+    objects
+        .text_fields
+        .iter()
+        .map(|f| TextStyle::new(f, objects))
+        .collect()
+}
+
+/// Font used to render text field previews.
+#[derive(Clone)]
+pub struct PreviewFont(FontArc);
+
+impl PreviewFont {
+    /// Returns the bundled fallback font, Ubuntu Light.
+    ///
+    /// The fonts used by the games are not stored in `.sc` files. Use [`from_data`]
+    /// to render previews with the actual font.
+    ///
+    /// [`from_data`]: #method.from_data
+    pub fn fallback() -> Self {
+        PreviewFont(FontArc::try_from_slice(FALLBACK_FONT).unwrap())
+    }
+
+    /// Loads a `TrueType` or `OpenType` font.
+    ///
+    /// If the font data is invalid, `Error::Unsupported` is raised.
+    ///
+    /// ## Arguments
+    ///
+    /// * `data`: Raw font file data.
+    pub fn from_data(data: Vec<u8>) -> Result<Self> {
+        FontArc::try_from_vec(data)
+            .map(PreviewFont)
+            .map_err(|e| Error::Unsupported(format!("invalid font: {}", e)))
+    }
+}
+
+/// Returns the width of a line of text.
+fn line_width<F: Font, S: ScaleFont<F>>(font: &S, line: &str) -> f32 {
+    let mut width = 0.0;
+    let mut previous = None;
+    for c in line.chars() {
+        let id = font.glyph_id(c);
+        if let Some(p) = previous {
+            width += font.kern(p, id);
+        }
+        width += font.h_advance(id);
+        previous = Some(id);
+    }
+    width
+}
+
+/// Splits text into lines. Lines of multiline text fields are wrapped at `max_width`.
+fn layout_lines<F: Font, S: ScaleFont<F>>(
+    font: &S,
+    text: &str,
+    max_width: f32,
+    multiline: bool,
+) -> Vec<String> {
+    if !multiline {
+        return vec![text.replace('\n', " ")];
+    }
+
+    let mut lines = Vec::new();
+    for paragraph in text.split('\n') {
+        let mut line = String::new();
+        for word in paragraph.split(' ') {
+            let candidate = if line.is_empty() {
+                word.to_owned()
+            } else {
+                format!("{} {}", line, word)
+            };
+            if !line.is_empty() && line_width(font, &candidate) > max_width {
+                lines.push(std::mem::replace(&mut line, word.to_owned()));
+            } else {
+                line = candidate;
+            }
+        }
+        lines.push(line);
+    }
+    lines
+}
+
+/// Spreads coverage to neighbouring pixels within `radius`.
+fn dilate(coverage: &[f32], width: usize, height: usize, radius: i32) -> Vec<f32> {
+    let mut dilated = coverage.to_vec();
+    for y in 0..height {
+        for x in 0..width {
+            let value = coverage[y * width + x];
+            if value == 0.0 {
+                continue;
+            }
+            for dy in -radius..=radius {
+                for dx in -radius..=radius {
+                    let (nx, ny) = (x as i32 + dx, y as i32 + dy);
+                    if nx < 0 || ny < 0 || nx >= width as i32 || ny >= height as i32 {
+                        continue;
+                    }
+                    let target = &mut dilated[ny as usize * width + nx as usize];
+                    *target = target.max(value);
+                }
+            }
+        }
+    }
+    dilated
+}
+
+/// Draws `color` over `canvas` with the given coverage.
+fn fill(canvas: &mut RgbaImage, coverage: &[f32], color: [u8; 4]) {
+    for (pixel, &c) in canvas.pixels_mut().zip(coverage) {
+        if c <= 0.0 {
+            continue;
+        }
+        let src_a = color[3] as f32 / 255.0 * c.min(1.0);
+        let dst_a = pixel.0[3] as f32 / 255.0 * (1.0 - src_a);
+        let out_a = src_a + dst_a;
+        for (channel, &value) in pixel.0.iter_mut().zip(&color).take(3) {
+            *channel = ((value as f32 * src_a + *channel as f32 * dst_a) / out_a).round() as u8;
+        }
+        pixel.0[3] = (out_a * 255.0).round() as u8;
+    }
+}
+
+/// Renders a preview of a text field with its default text.
+///
+/// The preview has the size of the text field's bounds. Text is laid out with the
+/// field's font size, alignment and style. Lines of multiline fields are wrapped.
+///
+/// `None` is returned if the text field has no default text. If the text field
+/// is too large to render, `Error::Unsupported` is raised.
+///
+/// ## Arguments
+///
+/// * `field`: The text field.
+/// * `font`: Font used to render the text.
+pub fn render_text_field(field: &TextField, font: &PreviewFont) -> Result<Option<RgbaImage>> {
+    let text = match field.text.as_deref().filter(|t| !t.is_empty()) {
+        Some(t) => t,
+        None => return Ok(None),
+    };
+
+    let (left, top, right, bottom) = field.bounds;
+    let width = (right as i32 - left as i32).max(1) as usize;
+    let height = (bottom as i32 - top as i32).max(1) as usize;
+    if width as f32 > MAX_SIZE || height as f32 > MAX_SIZE {
+        return Err(Error::Unsupported(format!(
+            "text field {} is too large to render ({}x{})",
+            field.id, width, height
+        )));
+    }
+
+    let scaled = font
+        .0
+        .as_scaled(PxScale::from(field.font_size.max(1) as f32));
+    let lines = layout_lines(&scaled, text, width as f32, field.multiline);
+    let line_height = scaled.height() + scaled.line_gap();
+    let total_height = line_height * lines.len() as f32 - scaled.line_gap();
+    let mut baseline = (height as f32 - total_height) / 2.0 + scaled.ascent();
+
+    let mut coverage = vec![0.0f32; width * height];
+    for line in &lines {
+        let line_width = line_width(&scaled, line);
+        let mut x = match align_name(field.align) {
+            "right" => width as f32 - line_width,
+            "center" => (width as f32 - line_width) / 2.0,
+            _ => 0.0,
+        };
+
+        let mut previous = None;
+        for c in line.chars() {
+            let id = scaled.glyph_id(c);
+            if let Some(p) = previous {
+                x += scaled.kern(p, id);
+            }
+            let glyph: Glyph = id.with_scale_and_position(scaled.scale(), (x, baseline));
+            x += scaled.h_advance(id);
+            previous = Some(id);
+
+            let outlined = match scaled.outline_glyph(glyph) {
+                Some(o) => o,
+                None => continue,
+            };
+            let bounds = outlined.px_bounds();
+            outlined.draw(|gx, gy, c| {
+                let py = bounds.min.y + gy as f32;
+                let shear = if field.italic {
+                    (baseline - py) * ITALIC_SHEAR
+                } else {
+                    0.0
+                };
+                let px = (bounds.min.x + gx as f32 + shear).round() as i32;
+                let py = py as i32;
+                if px >= 0 && py >= 0 && (px as usize) < width && (py as usize) < height {
+                    let target = &mut coverage[py as usize * width + px as usize];
+                    *target = (*target + c).min(1.0);
+                }
+            });
+        }
+        baseline += line_height;
+    }
+
+    if field.bold {
+        coverage = dilate(&coverage, width, height, 1);
+    }
+
+    let mut image = RgbaImage::new(width as u32, height as u32);
+    if field.outline {
+        let radius = (field.font_size as i32 / 10).max(2);
+        let outline = dilate(&coverage, width, height, radius);
+        fill(
+            &mut image,
+            &outline,
+            to_rgba(field.outline_color.unwrap_or(0xFF00_0000)),
+        );
+    }
+    fill(&mut image, &coverage, to_rgba(field.color));
+
+    Ok(Some(image))
+}
+
+/// Processes the text fields of a `.sc` object file.
+///
+/// The styles of all text fields are saved as `text_fields.json` in a directory
+/// named after the file inside the output directory (`out_dir`). If `font` is
+/// given, previews of text fields with default text are saved as well, named by
+/// the text field's first name followed by its id, or by its id alone. Text fields
+/// too large to preview are reported on `stdout` and skipped.
+///
+/// If saving is unsuccessful, the error is raised wrapped in `Error::File`.
+///
+/// [`decode_objects`]: objects/fn.decode_objects.html
+///
+/// ## Arguments
+///
+/// * `objects`: Objects of the `.sc` file, as returned by [`decode_objects`].
+/// * `path`: Path to the `.sc` file. It is used to get file name.
+/// * `out_dir`: Directory to store the text fields.
+/// * `font`: Font used to render previews, if previews are wanted.
+/// * `parallelize`: Whether files are processed in parallel or not.
+pub fn process_text_fields(
+    objects: &ScObjects,
+    path: &Path,
+    out_dir: &Path,
+    font: Option<&PreviewFont>,
+    parallelize: bool,
+) -> Result<()> {
+    let file_name = path.file_stem().unwrap().to_str().unwrap();
+
+    if !parallelize {
+        println!(
+            "\nExtracting text fields of {}...",
+            path.file_name().unwrap().to_str().unwrap().green().bold()
+        );
+    }
+
+    let styles = text_styles(objects);
+
+    let text_dir = out_dir.join(file_name);
+    fs::create_dir_all(&text_dir).map_err(|e| Error::from(e).in_file(&text_dir))?;
+
+    let json_path = text_dir.join("text_fields.json");
+    let file = fs::File::create(&json_path).map_err(|e| Error::from(e).in_file(&json_path))?;
+    serde_json::to_writer_pretty(BufWriter::new(file), &styles)
+        .map_err(|e| Error::from(e).in_file(&json_path))?;
+
+    if let Some(font) = font {
+        for (field, style) in objects.text_fields.iter().zip(&styles) {
+            let image = match render_text_field(field, font) {
+                Ok(Some(i)) => i,
+                Ok(None) => continue,
+                Err(e) => {
+                    println!(
+                        "{} {}",
+                        format!("Skipping text field preview in {}:", file_name).red(),
+                        e.to_string().red()
+                    );
+                    continue;
+                }
+            };
+            let name = object_file_name(
+                style.names.first().map(String::as_str),
+                "text_field",
+                field.id,
+            );
+            let out_path = text_dir.join(format!("{}.png", name));
+            image
+                .save(&out_path)
+                .map_err(|e| Error::from(e).in_file(&out_path))?;
+        }
+    }
+
+    println!(
+        "{} text field(s) extracted from {}",
+        styles.len().to_string().cyan().bold(),
+        file_name.green()
+    );
+
+    Ok(())
+}
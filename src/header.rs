@@ -0,0 +1,208 @@
+//! Header of Supercell's `.sc` container files.
+
+use crate::{
+    errors::{Error, Result},
+    utils::{decompress, LZHAM_MAGIC},
+};
+use byteorder::{BigEndian, ByteOrder};
+use std::ops::Range;
+
+/// Magic bytes at the start of `.sc` files.
+pub(crate) const SC_MAGIC: [u8; 2] = *b"SC";
+
+/// Magic bytes at the start of signed `.sc` files.
+pub(crate) const SIG_MAGIC: [u8; 4] = *b"Sig:";
+
+/// Length of the signature of signed `.sc` files.
+pub(crate) const SIGNATURE_LENGTH: usize = 64;
+
+/// Marker before the metadata block at the end of version 4 files.
+const METADATA_MARKER: &[u8] = b"START";
+
+/// Header of a `.sc` file.
+///
+/// All `.sc` files start with `SC`, followed by a big-endian `u32` version
+/// and a big-endian `u32` length-prefixed hash of the decompressed data.
+/// Version 4 files store the actual version in a second `u32` after the first
+/// one and end with a metadata block, which starts with a `START` marker.
+/// The compressed data lies between the header and the metadata block.
+///
+/// Signed files are wrapped in a signature block, which consists of `Sig:` and
+/// a 64-byte signature. The `SC` header follows the signature block.
+#[derive(Debug, Clone)]
+pub struct ScHeader {
+    /// Signature of signed files.
+    pub signature: Option<Vec<u8>>,
+    /// Version of the file.
+    pub version: u32,
+    /// Hash of the decompressed data. It is an `MD5` hash in all known files.
+    pub hash: Vec<u8>,
+    /// Metadata stored at the end of version 4 files, without the `START` marker.
+    pub metadata: Option<Vec<u8>>,
+    /// Position of the compressed data in the file.
+    payload: Range<usize>,
+}
+
+/// Checks if data starts with an `SC` header or a signature block.
+///
+/// `LZHAM` compressed data also starts with `SC` (`SCLZ`), but is not a container.
+pub(crate) fn is_container(data: &[u8]) -> bool {
+    data.starts_with(&SIG_MAGIC) || (data.starts_with(&SC_MAGIC) && !data.starts_with(&LZHAM_MAGIC))
+}
+
+/// Reads a big-endian `u32` from `data` at `offset`.
+fn read_u32(data: &[u8], offset: usize) -> Result<u32> {
+    match data.get(offset..offset + 4) {
+        Some(bytes) => Ok(BigEndian::read_u32(bytes)),
+        None => Err(Error::UnexpectedEof {
+            offset: data.len(),
+            needed: offset + 4 - data.len(),
+        }),
+    }
+}
+
+impl ScHeader {
+    /// Parses the header of raw `.sc` file data.
+    ///
+    /// The signature block of signed files is stripped and stored in `signature`.
+    ///
+    /// If the data doesn't start with `SC` (after the signature block, if any),
+    /// `Error::InvalidHeader` is raised. If the data ends before the header does,
+    /// `Error::UnexpectedEof` is raised.
+    ///
+    /// ## Arguments
+    ///
+    /// * `data`: Raw `.sc` file data.
+    pub fn parse(data: &[u8]) -> Result<Self> {
+        let mut offset = 0;
+        let mut signature = None;
+        if data.starts_with(&SIG_MAGIC) {
+            offset = SIG_MAGIC.len() + SIGNATURE_LENGTH;
+            match data.get(SIG_MAGIC.len()..offset) {
+                Some(s) => signature = Some(s.to_vec()),
+                None => {
+                    return Err(Error::UnexpectedEof {
+                        offset: data.len(),
+                        needed: offset - data.len(),
+                    })
+                }
+            }
+        }
+
+        if !data[offset..].starts_with(&SC_MAGIC) {
+            return Err(Error::InvalidHeader("missing `SC` magic".to_owned()));
+        }
+
+        offset += SC_MAGIC.len();
+        let mut version = read_u32(data, offset)?;
+        offset += 4;
+
+        let has_metadata = version == 4;
+        if has_metadata {
+            version = read_u32(data, offset)?;
+            offset += 4;
+        }
+
+        let hash_length = read_u32(data, offset)? as usize;
+        offset += 4;
+
+        let hash = match data.get(offset..offset.saturating_add(hash_length)) {
+            Some(h) => h.to_vec(),
+            None => {
+                return Err(Error::UnexpectedEof {
+                    offset: data.len(),
+                    needed: offset + hash_length - data.len(),
+                })
+            }
+        };
+        offset += hash_length;
+
+        let mut end = data.len();
+        let mut metadata = None;
+        if has_metadata {
+            match data[offset..]
+                .windows(METADATA_MARKER.len())
+                .rposition(|w| w == METADATA_MARKER)
+            {
+                Some(pos) => {
+                    end = offset + pos;
+                    metadata = Some(data[end + METADATA_MARKER.len()..].to_vec());
+                }
+                None => {
+                    return Err(Error::InvalidHeader(
+                        "missing `START` marker of metadata".to_owned(),
+                    ))
+                }
+            }
+        }
+
+        Ok(Self {
+            signature,
+            version,
+            hash,
+            metadata,
+            payload: offset..end,
+        })
+    }
+
+    /// Size of the header in bytes, including the signature block.
+    pub fn size(&self) -> usize {
+        self.payload.start
+    }
+
+    /// Returns the compressed data of `data`, the file this header was parsed from.
+    ///
+    /// An empty slice is returned if `data` is smaller than the parsed file.
+    ///
+    /// ## Arguments
+    ///
+    /// * `data`: Raw `.sc` file data.
+    pub fn payload<'a>(&self, data: &'a [u8]) -> &'a [u8] {
+        data.get(self.payload.clone()).unwrap_or(&[])
+    }
+
+    /// Verifies decompressed data against the hash in the header.
+    ///
+    /// The `MD5` hash of `decompressed` is compared with the header's hash. If they
+    /// don't match, `Error::HashMismatch` is raised. Headers with hashes that are not
+    /// 16 bytes long can't be verified, so `Error::Unsupported` is raised for them.
+    ///
+    /// ## Arguments
+    ///
+    /// * `decompressed`: Decompressed data of the file this header was parsed from.
+    pub fn verify(&self, decompressed: &[u8]) -> Result<()> {
+        if self.hash.len() != 16 {
+            return Err(Error::Unsupported(format!(
+                "{}-byte hash can't be verified",
+                self.hash.len()
+            )));
+        }
+
+        let actual = md5::compute(decompressed);
+        if actual.0[..] == self.hash[..] {
+            Ok(())
+        } else {
+            Err(Error::HashMismatch {
+                expected: self.hash.clone(),
+                actual: actual.0.to_vec(),
+            })
+        }
+    }
+}
+
+/// Verifies the integrity of raw `.sc` file data.
+///
+/// The data is decompressed and its hash is compared with the hash stored in
+/// the header. See [`ScHeader::verify`].
+///
+/// [`ScHeader::verify`]: struct.ScHeader.html#method.verify
+///
+/// ## Arguments
+///
+/// * `data`: Raw `.sc` file data.
+pub fn verify_sc(data: &[u8]) -> Result<()> {
+    let header = ScHeader::parse(data)?;
+    let decompressed = decompress(header.payload(data))?;
+
+    header.verify(decompressed.get_ref())
+}
@@ -4,6 +4,21 @@
 //! `_tex.sc` and `.csv` files respectively. The functions only process valid, compressed/encoded
 //! and raw files found directly in Supercell apps.
 //!
+//! `decode_tex` and `decode_csv` can be used to decode `_tex.sc` and `.csv` data in memory,
+//! without writing anything to the disk. `decode_objects` decodes the shapes, movie clips
+//! and text fields of other `.sc` files into the types of the [`objects`] module, and
+//! `decode_sprites` cuts their shapes out of the textures of the companion `_tex.sc` file.
+//! `decode_animations` renders their exported movie clips into animations, which
+//! `pack_sheet` packs into sprite sheets. `export_svgs` exports shapes and movie clips as
+//! `SVG` documents referring to the extracted texture atlases. `text_styles` and
+//! `render_text_field` export the styling of text fields. `dump_sc` dumps the tags of
+//! any `.sc` file for inspection.
+//! `decode_ktx` decodes the `KTX` images which newer `_tex.sc` files store their textures in,
+//! and `decode_pvr` the `PVR` images of older iOS builds. `decode_image` and `process_image`
+//! handle both kinds of standalone texture files.
+//!
+//! [`objects`]: objects/index.html
+//!
 //! This library is simply intended to get high quality graphics and data from the files.
 //! It is in no way an attempt to:
 //!
@@ -11,9 +26,35 @@
 //! - create a clone or any other game based on Supercell games
 //! - make profit
 
+mod detect;
 pub mod errors;
 mod extractors;
+mod header;
 mod utils;
 
 #[doc(inline)]
-pub use extractors::{csv::process_csv, tex::process_sc};
+pub use detect::{detect, detect_file, Compression, Detected, FileKind};
+#[doc(inline)]
+pub use errors::{Error, Result};
+#[doc(inline)]
+pub use extractors::{
+    animation::{
+        decode_animations, process_animations, render_animations, render_movie_clip, Animation,
+        AnimationFormat, RenderOptions,
+    },
+    csv::{decode_csv, decode_csv_records, process_csv, save_csv},
+    dump::{self, dump_sc, Dump},
+    ktx::decode_ktx,
+    objects::{self, decode_objects, ScObjects},
+    pvr::decode_pvr,
+    sheet::{self, pack_sheet, process_sheets, SpriteSheet},
+    sprites::{cut_sprites, decode_sprites, process_sprites, render_shape, Sprite},
+    svg::{atlas_names, export_svgs, movie_clip_to_svg, process_svgs, shape_to_svg, SvgDocument},
+    tex::{
+        decode_image, decode_tex, decode_tex_blocks, decode_tex_blocks_in, parse_tex_blocks,
+        process_image, process_sc, process_sc_with_mipmaps, save_tex_blocks, PixelFormat, Texture,
+    },
+    text::{process_text_fields, render_text_field, text_styles, PreviewFont, TextStyle},
+};
+#[doc(inline)]
+pub use header::{verify_sc, ScHeader};
@@ -1,19 +1,28 @@
 use colored::Colorize;
 use rayon::prelude::*;
-use sc_extract::{process_csv, process_sc};
+use sc_extract::{
+    decode_tex_blocks_in, detect_file, dump_sc, objects::parse_objects, parse_tex_blocks,
+    process_animations, process_image, process_sheets, process_sprites, process_svgs,
+    process_text_fields, save_csv, save_tex_blocks, verify_sc, AnimationFormat, Detected, Error,
+    FileKind, PreviewFont, RenderOptions, ScObjects,
+};
 use std::{
     fs,
-    path::PathBuf,
-    sync::atomic::{AtomicBool, Ordering},
+    path::{Path, PathBuf},
+};
+use structopt::{
+    clap::{AppSettings, ErrorKind},
+    StructOpt,
 };
-use structopt::StructOpt;
 
 #[derive(StructOpt)]
+#[structopt(setting = AppSettings::ArgsNegateSubcommands)]
 struct Options {
     /// The path to directory containing `_tex.sc` or `.csv` files or
-    /// path to an `_tex.sc` or `.csv` file.
+    /// path to an `_tex.sc` or `.csv` file. File types are detected from
+    /// the contents of the files, so the files can have any name.
     #[structopt(parse(from_os_str))]
-    path: PathBuf,
+    path: Option<PathBuf>,
 
     /// The path to directory where an extracts folder is created to save output.
     /// If not specified, `extracts` is created inside `path`.
@@ -21,23 +30,132 @@ struct Options {
     out_dir: Option<PathBuf>,
 
     /// If this flag is supplied, the source `_tex.sc` or `.csv` files are deleted after extracting.
+    /// Files of a directory are deleted once all of them are extracted.
     #[structopt(short = "d", long = "delete")]
     delete: bool,
 
     /// Extracts all images in parallel. It makes the process faster.
     #[structopt(short = "p", long = "parallelize")]
     parallelize: bool,
+
+    /// Verifies the hash of `.sc` files before extracting. Files with
+    /// mismatching hashes are reported as failures and not extracted. Files
+    /// without an `SC` header have no hash to verify, so a warning is printed
+    /// and they are extracted as usual.
+    #[structopt(short = "c", long = "verify")]
+    verify: bool,
+
+    /// Also saves the mipmap levels stored after textures, as `_mip1.png`,
+    /// `_mip2.png` and so on.
+    #[structopt(long = "mipmaps")]
+    mipmaps: bool,
+
+    /// Cuts the shapes of `.sc` object files out of the textures of their
+    /// companion `_tex.sc` files and saves one image per shape.
+    #[structopt(short = "s", long = "sprites")]
+    sprites: bool,
+
+    /// Renders the exported movie clips of `.sc` object files into animations,
+    /// using the textures of their companion `_tex.sc` files. Valid formats
+    /// are `gif` and `apng`.
+    #[structopt(short = "a", long = "animate")]
+    animate: Option<AnimationFormat>,
+
+    /// Renders the exported movie clips of `.sc` object files into sprite sheets
+    /// with `JSON` descriptions in the TexturePacker/Phaser format.
+    #[structopt(long = "sheets")]
+    sheets: bool,
+
+    /// Scale at which movie clips are rendered. Must be a positive number.
+    #[structopt(long = "scale", default_value = "1.0", parse(try_from_str = parse_scale))]
+    scale: f32,
+
+    /// Frame rate of rendered animations. Defaults to the frame rate of each movie clip.
+    #[structopt(long = "fps")]
+    fps: Option<u8>,
+
+    /// Exports the shapes and exported movie clips of `.sc` object files as SVG
+    /// documents which refer to the textures extracted from the companion `_tex.sc` files.
+    #[structopt(long = "svg")]
+    svg: bool,
+
+    /// Exports all frames of movie clips into SVG documents instead of the first one.
+    /// Implies `--svg`.
+    #[structopt(long = "all-frames")]
+    all_frames: bool,
+
+    /// Saves the styles of the text fields of `.sc` object files as JSON.
+    #[structopt(short = "t", long = "text-fields")]
+    text_fields: bool,
+
+    /// Renders previews of text fields with their default text. Implies `--text-fields`.
+    #[structopt(long = "preview")]
+    preview: bool,
+
+    /// The path to a font used to render text field previews. If not specified,
+    /// a bundled fallback font is used.
+    #[structopt(parse(from_os_str), long = "font")]
+    font: Option<PathBuf>,
+
+    #[structopt(subcommand)]
+    command: Option<Command>,
 }
 
-/// Checks if file path ends with `_tex.sc` or `.csv`.
-fn is_valid_file(path: &PathBuf) -> bool {
-    path.to_str().unwrap().ends_with("_tex.sc") || path.to_str().unwrap().ends_with(".csv")
+impl Options {
+    /// Whether any of the options processing `.sc` object files was supplied.
+    fn processes_objects(&self) -> bool {
+        self.sprites
+            || self.sheets
+            || self.animate.is_some()
+            || self.text_fields
+            || self.preview
+            || self.svg
+            || self.all_frames
+    }
+
+    /// Whether any of the options which need the companion `_tex.sc` file was supplied.
+    fn needs_textures(&self) -> bool {
+        self.sprites || self.sheets || self.animate.is_some()
+    }
+}
+
+#[derive(StructOpt)]
+enum Command {
+    /// Dumps the tags of a `.sc` or `_tex.sc` file as JSON, for inspection.
+    Dump {
+        /// The path to the `.sc` or `_tex.sc` file.
+        #[structopt(parse(from_os_str))]
+        path: PathBuf,
+
+        /// The path to the file where the JSON is saved. If not specified,
+        /// the JSON is printed on stdout.
+        #[structopt(parse(from_os_str), short = "o", long = "out")]
+        out: Option<PathBuf>,
+    },
+}
+
+/// Parses the `--scale` option, rejecting values which are not finite and positive.
+fn parse_scale(value: &str) -> Result<f32, String> {
+    match value.parse::<f32>() {
+        Ok(scale) if scale.is_finite() && scale > 0.0 => Ok(scale),
+        Ok(_) => Err(format!("scale must be a positive number, got `{}`", value)),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+/// Reason why a file was not extracted.
+#[derive(Debug, PartialEq, Eq)]
+enum Skipped {
+    /// The file has nothing to extract.
+    Unsupported,
+    /// The file couldn't be read or failed verification.
+    Failed,
 }
 
 /// Deletes the file with given path.
 /// It deletion fails, prints it on stdout.
-fn delete_file(path: &PathBuf) {
-    match fs::remove_file(&path) {
+fn delete_file(path: &Path) {
+    match fs::remove_file(path) {
         Ok(_) => (),
         Err(_) => println!(
             "{}: {}",
@@ -47,82 +165,243 @@ fn delete_file(path: &PathBuf) {
     };
 }
 
-/// Checks if data has correct header and returns Option<header_type>,
-/// where header_type can be "sc" or "csv", depending on the data.
-///
-/// The data passed here must be compressed/raw.
-fn check_header(data: &[u8]) -> Option<&'static str> {
-    if data.is_empty() {
-        None
-    } else if data[0] == 83 {
-        Some("sc")
-    } else if data[..2] == [93, 0] {
-        Some("csv")
-    } else {
-        None
+/// Returns the path of the `_tex.sc` file belonging to a `.sc` object file.
+fn companion_tex_path(path: &Path) -> PathBuf {
+    let stem = path.file_stem().unwrap().to_str().unwrap();
+    path.with_file_name(format!("{}_tex.sc", stem))
+}
+
+/// Extracts the text fields, exports the SVG documents, cuts the sprites, renders the
+/// movie clips and packs the sprite sheets of a `.sc` object file, as requested by `opts`.
+/// Everything but the text fields and SVG documents is drawn using the companion
+/// `_tex.sc` file.
+fn process_object_file(
+    objects: &ScObjects,
+    path: &Path,
+    out_dir: &Path,
+    opts: &Options,
+    font: Option<&PreviewFont>,
+    parallelize: bool,
+) {
+    if opts.text_fields || opts.preview {
+        if let Err(e) = process_text_fields(objects, path, out_dir, font, parallelize) {
+            println!("\n{}", e.to_string().red());
+        }
+    }
+
+    if opts.svg || opts.all_frames {
+        if let Err(e) = process_svgs(objects, path, out_dir, opts.all_frames, parallelize) {
+            println!("\n{}", e.to_string().red());
+        }
+    }
+
+    if !opts.needs_textures() {
+        return;
+    }
+
+    let tex_path = companion_tex_path(path);
+    let tex_dir = tex_path.parent().unwrap_or_else(|| Path::new("."));
+    let textures: Vec<_> = match fs::read(&tex_path)
+        .map_err(Error::from)
+        .and_then(|tex_data| decode_tex_blocks_in(&tex_data, tex_dir))
+    {
+        Ok(blocks) => blocks.into_iter().map(|t| t.ok()).collect(),
+        Err(e) => {
+            println!("\n{}", e.in_file(tex_path).to_string().red());
+            return;
+        }
+    };
+
+    if opts.sprites {
+        if let Err(e) = process_sprites(objects, &textures, path, out_dir, parallelize) {
+            println!("\n{}", e.to_string().red());
+        }
+    }
+
+    let options = RenderOptions {
+        scale: opts.scale,
+        fps: opts.fps,
+    };
+    // Movie clips are rendered once for both sheets and animations.
+    if opts.sheets {
+        if let Err(e) = process_sheets(
+            objects,
+            &textures,
+            path,
+            out_dir,
+            opts.animate,
+            &options,
+            parallelize,
+        ) {
+            println!("\n{}", e.to_string().red());
+        }
+    } else if let Some(format) = opts.animate {
+        if let Err(e) = process_animations(
+            objects,
+            &textures,
+            path,
+            out_dir,
+            format,
+            &options,
+            parallelize,
+        ) {
+            println!("\n{}", e.to_string().red());
+        }
     }
 }
 
 /// Processes the given file (path).
 ///
-/// It automatically detects file type (`_tex.sc` or `.csv`) and processes them appropriately.
-/// If processing a file fails, formatted error messages gets printed on `stdout`.
-/// In case of lack of permissions, the process may panic.
+/// It automatically detects file type from the file's contents and processes `_tex.sc`
+/// and `.csv` files appropriately. If sprites or animations are requested, `.sc`
+/// object files are processed as well. Files of other types are reported on `stdout`.
+/// The data decompressed to detect the type is reused, so each file is decompressed
+/// only once. If processing a file fails, formatted error messages gets printed on
+/// `stdout`. In case of lack of permissions, the process may panic.
+///
+/// Files which can't be read or fail verification return `Skipped::Failed`, and
+/// files with nothing to extract `Skipped::Unsupported`.
 ///
 /// ## Arguments
 ///
 /// * `path`: Reference to the file path.
 /// * `out_dir`: Path to directory where `extracts` folder is created to store extracts.
-/// * `delete`: Whether to delete file after extraction or not.
+/// * `opts`: Command-line options, such as whether to verify the file before extraction.
+/// * `font`: Font used to render text field previews, if previews are wanted.
 /// * `parallelize`: Whether files are processed in parallel or not.
 fn process_file(
-    path: &PathBuf,
-    out_dir: &PathBuf,
-    delete: bool,
+    path: &Path,
+    out_dir: &Path,
+    opts: &Options,
+    font: Option<&PreviewFont>,
     parallelize: bool,
-) -> Result<(), ()> {
-    let data = fs::read(&path).unwrap();
+) -> Result<(), Skipped> {
+    let data = match fs::read(path) {
+        Ok(d) => d,
+        Err(e) => {
+            println!("\n{}", Error::from(e).in_file(path).to_string().red());
+            return Err(Skipped::Failed);
+        }
+    };
 
-    let process = check_header(data.as_slice());
+    let Detected {
+        kind,
+        header,
+        decompressed,
+    } = detect_file(&data);
+
+    if opts.verify {
+        let result = match (&header, &decompressed) {
+            (Some(header), Some(decompressed)) => header.verify(decompressed),
+            // Decompression failed, which `verify_sc` reports.
+            (Some(_), None) => verify_sc(&data),
+            (None, _) => {
+                println!(
+                    "\n{}",
+                    format!(
+                        "No hash to verify in {} file, extracting it anyway: {}",
+                        kind,
+                        path.to_str().unwrap().bold()
+                    )
+                    .yellow()
+                );
+                Ok(())
+            }
+        };
+        if let Err(e) = result {
+            println!(
+                "\n{} {}",
+                "Skipping file that failed verification:".red(),
+                e.in_file(path).to_string().red()
+            );
+            return Err(Skipped::Failed);
+        }
+    }
 
-    let process_fn = match process {
-        Some("sc") => process_sc,
-        Some("csv") => process_csv,
+    let result = match (kind, decompressed) {
+        (FileKind::ScObject, Some(decompressed)) if opts.processes_objects() => {
+            match parse_objects(decompressed) {
+                Ok(objects) => {
+                    process_object_file(&objects, path, out_dir, opts, font, parallelize);
+                    Ok(())
+                }
+                Err(e) => Err(e.in_file(path)),
+            }
+        }
+        (FileKind::ScTexture, Some(decompressed)) => {
+            let dir = path.parent().unwrap_or_else(|| Path::new("."));
+            let blocks = parse_tex_blocks(decompressed, Some(dir));
+            save_tex_blocks(blocks, path, out_dir, parallelize, opts.mipmaps)
+        }
+        (FileKind::CompressedCsv, Some(decompressed)) => save_csv(&decompressed, path, out_dir),
+        (FileKind::Ktx, _) | (FileKind::Pvr, _) => process_image(&data, path, out_dir, parallelize),
         _ => {
             println!(
                 "{}",
                 format!(
-                    "File has `_tex.sc` or `.csv` extension but is actually of unknown type: {}",
+                    "Found {} file, nothing to extract: {}",
+                    kind,
                     path.to_str().unwrap().bold()
                 )
                 .yellow()
             );
-            return Err(());
+            return Err(Skipped::Unsupported);
         }
     };
 
-    match process_fn(&data, &path, &out_dir, parallelize) {
-        Ok(_) => (),
-        Err(e) => println!("\n{} {}", e.0.red(), path.to_str().unwrap().red()),
-    };
-
-    if delete {
-        delete_file(&path);
+    if let Err(e) = result {
+        println!("\n{}", e.to_string().red());
     }
 
     Ok(())
 }
 
+/// Dumps the tags of the given file as JSON, to `out` or `stdout`.
+/// Exits the process if dumping fails.
+fn dump_file(path: &Path, out: Option<&Path>) {
+    let json = fs::read(path)
+        .map_err(Error::from)
+        .and_then(|data| dump_sc(&data))
+        .and_then(|dump| dump.to_json())
+        .map_err(|e| e.in_file(path));
+
+    let result = json.and_then(|json| match out {
+        Some(out) => fs::write(out, json).map_err(|e| Error::from(e).in_file(out)),
+        None => {
+            println!("{}", json);
+            Ok(())
+        }
+    });
+
+    if let Err(e) = result {
+        eprintln!("{}", e.to_string().red());
+        std::process::exit(1);
+    }
+}
+
 fn main() {
     let opts: Options = Options::from_args();
 
+    if let Some(Command::Dump { path, out }) = &opts.command {
+        dump_file(path, out.as_deref());
+        return;
+    }
+    let path = match &opts.path {
+        Some(p) => p,
+        None => structopt::clap::Error::with_description(
+            "The following required argument was not provided: <path>",
+            ErrorKind::MissingRequiredArgument,
+        )
+        .exit(),
+    };
+
     let out_dir = match &opts.out_dir {
         Some(p) => p.join("extracts"),
         None => {
-            if opts.path.is_dir() {
-                opts.path.join("extracts")
-            } else if opts.path.is_file() {
-                opts.path.parent().unwrap().join("extracts")
+            if path.is_dir() {
+                path.join("extracts")
+            } else if path.is_file() {
+                path.parent().unwrap().join("extracts")
             } else {
                 std::env::current_dir().unwrap().join("extracts")
             }
@@ -133,44 +412,68 @@ fn main() {
         fs::create_dir_all(&out_dir).unwrap();
     }
 
-    if opts.path.is_dir() {
-        let found_one = AtomicBool::new(false);
-        let dir_entries = match fs::read_dir(&opts.path) {
+    let font = if opts.preview {
+        Some(match &opts.font {
+            Some(font_path) => {
+                match fs::read(font_path)
+                    .map_err(Error::from)
+                    .and_then(PreviewFont::from_data)
+                {
+                    Ok(f) => f,
+                    Err(e) => {
+                        println!("{}", e.in_file(font_path).to_string().red());
+                        std::process::exit(1);
+                    }
+                }
+            }
+            None => PreviewFont::fallback(),
+        })
+    } else {
+        None
+    };
+
+    if path.is_dir() {
+        let dir_entries = match fs::read_dir(path) {
             Ok(e) => e,
             Err(_) => {
                 println!(
                     "{}",
                     format!(
                         "Failed to read contents of {} directory/folder.",
-                        opts.path.to_str().unwrap().red()
+                        path.to_str().unwrap().red()
                     )
                     .red()
                 );
                 std::process::exit(1);
             }
         };
-        let mut entries = Vec::new();
-        for entry in dir_entries {
-            entries.push(entry);
-        }
-        if opts.parallelize {
-            entries.into_par_iter().for_each(|entry| {
-                let path = entry.unwrap().path();
-                if is_valid_file(&path) && process_file(&path, &out_dir, opts.delete, true).is_ok()
-                {
-                    found_one.compare_and_swap(false, true, Ordering::AcqRel);
-                }
-            })
+        let files: Vec<PathBuf> = dir_entries
+            .map(|entry| entry.unwrap().path())
+            .filter(|path| path.is_file())
+            .collect();
+        let results: Vec<_> = if opts.parallelize {
+            files
+                .par_iter()
+                .map(|path| process_file(path, &out_dir, &opts, font.as_ref(), true))
+                .collect()
         } else {
-            for entry in entries {
-                let path = entry.unwrap().path();
-                if is_valid_file(&path) && process_file(&path, &out_dir, opts.delete, false).is_ok()
-                {
-                    found_one.compare_and_swap(false, true, Ordering::AcqRel);
+            files
+                .iter()
+                .map(|path| process_file(path, &out_dir, &opts, font.as_ref(), false))
+                .collect()
+        };
+
+        // Files are deleted once all of them are processed, as `.sc` object files
+        // need their `_tex.sc` files, and these their external textures.
+        if opts.delete {
+            for (path, result) in files.iter().zip(&results) {
+                if result.is_ok() {
+                    delete_file(path);
                 }
             }
         }
-        if !found_one.into_inner() {
+
+        if !results.iter().any(Result::is_ok) {
             println!(
                 "{}",
                 "No valid `_tex.sc` or `.csv` file in the given directory!"
@@ -179,20 +482,22 @@ fn main() {
             );
             std::process::exit(1);
         }
-    } else if opts.path.is_file() {
-        if is_valid_file(&opts.path) {
-            if process_file(&opts.path, &out_dir, opts.delete, false).is_err() {
-                return;
-            }
-        } else {
+        if results.contains(&Err(Skipped::Failed)) {
             println!(
-                "{}",
-                "Given file doesn't appear to be an `_tex.sc` or `.csv` file!"
+                "\n{}",
+                "Some files could not be read or failed verification!"
                     .red()
                     .bold()
             );
             std::process::exit(1);
         }
+    } else if path.is_file() {
+        if process_file(path, &out_dir, &opts, font.as_ref(), false).is_err() {
+            std::process::exit(1);
+        }
+        if opts.delete {
+            delete_file(path);
+        }
     }
 
     println!("\n{}", "Extraction finished!".green().bold());
@@ -1,133 +1,191 @@
-use super::errors::DecompressionError;
+use crate::errors::{Error, Result};
 use byteorder::{LittleEndian, ReadBytesExt};
+use image::{Rgba, RgbaImage};
 use lzma_rs::lzma_decompress;
+use ruzstd::StreamingDecoder;
 use std::io::{Cursor, Read};
 
 /// Wrapper for reading data from stream.
+///
+/// All reads are bounds-checked. Reading past the end of the data raises
+/// `Error::UnexpectedEof` and leaves the stream untouched.
 pub(crate) struct Reader {
     stream: Cursor<Vec<u8>>,
-    bytes_left: usize,
+    /// Offset of the stream in the data it was taken from. It is non-zero for
+    /// readers created by `sub_reader`, so that reported offsets are absolute.
+    base: usize,
 }
 
 impl Reader {
     /// Create new `Reader` instance from a stream.
     pub fn new(stream: Cursor<Vec<u8>>) -> Self {
-        let bytes_left = stream.get_ref().len();
-
-        Self { stream, bytes_left }
+        Self { stream, base: 0 }
     }
 
     /// Bytes left in the data stream.
     pub fn len(&self) -> usize {
-        self.bytes_left
+        self.stream
+            .get_ref()
+            .len()
+            .saturating_sub(self.stream.position() as usize)
     }
 
-    /// Read exact number of bytes from the stream.
-    pub fn read(&mut self, size: usize) -> Vec<u8> {
-        if size > self.bytes_left {
-            self.bytes_left = 0;
+    /// Checks that `size` bytes can be read from the stream.
+    fn ensure(&self, size: usize) -> Result<()> {
+        let left = self.len();
+        if size > left {
+            Err(Error::UnexpectedEof {
+                offset: self.tell(),
+                needed: size - left,
+            })
         } else {
-            self.bytes_left -= size;
+            Ok(())
         }
+    }
+
+    /// Read exact number of bytes from the stream.
+    pub fn read(&mut self, size: usize) -> Result<Vec<u8>> {
+        self.ensure(size)?;
 
         let mut buf = vec![0; size];
-        if self.bytes_left == 0 {
-            self.stream.read_to_end(&mut buf).unwrap();
+        self.stream.read_exact(&mut buf)?;
 
-            buf
-        } else {
-            self.stream.read_exact(&mut buf).unwrap();
+        Ok(buf)
+    }
 
-            buf
-        }
+    /// Read `size` bytes from the stream and return a new `Reader` limited to them.
+    ///
+    /// Offsets reported by the new reader are relative to the start of this
+    /// reader's data, not the new reader's.
+    pub fn sub_reader(&mut self, size: usize) -> Result<Reader> {
+        let base = self.tell();
+
+        Ok(Reader {
+            stream: Cursor::new(self.read(size)?),
+            base,
+        })
     }
 
     /// Read one byte from the stream.
-    pub fn read_byte(&mut self) -> u8 {
-        if 1 > self.bytes_left {
-            self.bytes_left = 0;
-        } else {
-            self.bytes_left -= 1;
-        }
+    pub fn read_byte(&mut self) -> Result<u8> {
+        self.ensure(1)?;
 
-        self.stream.read_u8().unwrap()
+        Ok(self.stream.read_u8()?)
+    }
+
+    /// Read one byte from the stream and return whether it is non-zero.
+    pub fn read_bool(&mut self) -> Result<bool> {
+        Ok(self.read_byte()? != 0)
     }
 
     /// Read an unsigned 16-bit little-endian integer from the stream.
-    pub fn read_uint16(&mut self) -> u16 {
-        if 2 > self.bytes_left {
-            self.bytes_left = 0;
-        } else {
-            self.bytes_left -= 2;
-        }
+    pub fn read_uint16(&mut self) -> Result<u16> {
+        self.ensure(2)?;
 
-        self.stream.read_u16::<LittleEndian>().unwrap()
+        Ok(self.stream.read_u16::<LittleEndian>()?)
     }
 
     /// Read an unsigned 32-bit little-endian integer from the stream.
-    pub fn read_uint32(&mut self) -> u32 {
-        if 4 > self.bytes_left {
-            self.bytes_left = 0;
-        } else {
-            self.bytes_left -= 4;
-        }
+    pub fn read_uint32(&mut self) -> Result<u32> {
+        self.ensure(4)?;
 
-        self.stream.read_u32::<LittleEndian>().unwrap()
+        Ok(self.stream.read_u32::<LittleEndian>()?)
     }
 
     /// Read an signed 16-bit little-endian integer from the stream.
-    pub fn _read_int16(&mut self) -> i16 {
-        if 2 > self.bytes_left {
-            self.bytes_left = 0;
-        } else {
-            self.bytes_left -= 2;
-        }
+    pub fn read_int16(&mut self) -> Result<i16> {
+        self.ensure(2)?;
 
-        self.stream.read_i16::<LittleEndian>().unwrap()
+        Ok(self.stream.read_i16::<LittleEndian>()?)
     }
 
     /// Read an signed 32-bit little-endian integer from the stream.
-    pub fn _read_int32(&mut self) -> i32 {
-        if 4 > self.bytes_left {
-            self.bytes_left = 0;
-        } else {
-            self.bytes_left -= 4;
-        }
+    pub fn read_int32(&mut self) -> Result<i32> {
+        self.ensure(4)?;
 
-        self.stream.read_i32::<LittleEndian>().unwrap()
+        Ok(self.stream.read_i32::<LittleEndian>()?)
+    }
+
+    /// Read a signed 32-bit little-endian integer in twips from the stream
+    /// and return it in pixels. There are 20 twips in a pixel.
+    pub fn read_twip(&mut self) -> Result<f32> {
+        Ok(self.read_int32()? as f32 / 20.0)
     }
 
     /// Read `length` bytes from the stream and return the output as a `String`.
-    pub fn _read_string(&mut self, length: usize) -> String {
-        if length > self.bytes_left {
-            self.bytes_left = 0;
-        } else {
-            self.bytes_left -= length;
+    pub fn read_string(&mut self, length: usize) -> Result<String> {
+        Ok(String::from_utf8_lossy(self.read(length)?.as_slice()).to_string())
+    }
+
+    /// Read a string prefixed with its length as one byte.
+    ///
+    /// A length of `255` denotes a missing string, for which `None` is returned.
+    pub fn read_ascii(&mut self) -> Result<Option<String>> {
+        match self.read_byte()? {
+            255 => Ok(None),
+            length => Ok(Some(self.read_string(length as usize)?)),
         }
+    }
 
-        String::from_utf8_lossy(self.read(length).as_slice()).to_string()
+    /// Returns the bytes left in the stream without reading them.
+    pub fn remaining(&self) -> &[u8] {
+        let data = self.stream.get_ref();
+        &data[data.len() - self.len()..]
     }
 
     /// Returns the current position of this cursor as usize.
-    pub fn _tell(&self) -> usize {
-        self.stream.position() as usize
+    pub fn tell(&self) -> usize {
+        self.base + self.stream.position() as usize
     }
 }
 
+/// Magic bytes at the start of a `Zstandard` frame.
+pub(crate) const ZSTD_MAGIC: [u8; 4] = [0x28, 0xB5, 0x2F, 0xFD];
+
+/// Magic bytes at the start of `LZHAM` compressed data.
+pub(crate) const LZHAM_MAGIC: [u8; 4] = *b"SCLZ";
+
 /// Decompress proper `.tex_sc` or `.csv` data.
 ///
-/// Before decompressing the data using `LZMA` decompression,
-/// four `\x00` bytes are added to `raw_data` after the eigth index.
-/// A `Cursor` containing the transformed raw data is returned, wrapped up in `Ok`.
+/// The compression method is detected from the data. Data starting with a
+/// `Zstandard` frame is decompressed using `Zstandard` and data starting with
+/// `SCLZ` is treated as `LZHAM`. Any other data is assumed to use `LZMA`.
+/// See [`decompress_lzma`], [`decompress_zstd`] and [`decompress_lzham`].
 ///
 /// Supercell game `_tex.sc` files require the header to be removed before decompression.
 ///
-/// If the decompression fails due to any reason, `DecompressionError` is raised.
+/// If the decompression fails due to any reason, `Error::Decompression` is raised.
 ///
 /// ## Arguments
 ///
 /// * `raw_data`: Proper `_tex.sc` or `.csv` raw file data.
-pub(crate) fn decompress(raw_data: &[u8]) -> Result<Cursor<Vec<u8>>, DecompressionError> {
+pub(crate) fn decompress(raw_data: &[u8]) -> Result<Cursor<Vec<u8>>> {
+    if raw_data.starts_with(&ZSTD_MAGIC) {
+        decompress_zstd(raw_data)
+    } else if raw_data.starts_with(&LZHAM_MAGIC) {
+        decompress_lzham(raw_data)
+    } else {
+        decompress_lzma(raw_data)
+    }
+}
+
+/// Decompress `LZMA` compressed `.tex_sc` or `.csv` data.
+///
+/// Before decompressing the data using `LZMA` decompression,
+/// four `\x00` bytes are added to `raw_data` after the eigth index.
+/// A `Cursor` containing the transformed raw data is returned, wrapped up in `Ok`.
+///
+/// ## Arguments
+///
+/// * `raw_data`: `LZMA` compressed data.
+fn decompress_lzma(raw_data: &[u8]) -> Result<Cursor<Vec<u8>>> {
+    if raw_data.len() < 9 {
+        return Err(Error::UnexpectedEof {
+            offset: raw_data.len(),
+            needed: 9 - raw_data.len(),
+        });
+    }
+
     let mut data = raw_data[0..9].to_vec();
 
     data.append(&mut vec![b'\x00'; 4]);
@@ -136,6 +194,130 @@ pub(crate) fn decompress(raw_data: &[u8]) -> Result<Cursor<Vec<u8>>, Decompressi
     let mut decomp: Vec<u8> = Vec::new();
     match lzma_decompress(&mut data.as_slice(), &mut decomp) {
         Ok(_) => Ok(Cursor::new(decomp)),
-        Err(_) => Err(DecompressionError("Failed to decompress file:".to_owned())),
+        Err(e) => Err(Error::Decompression(e.to_string())),
+    }
+}
+
+/// Decompress `Zstandard` compressed `.tex_sc` or `.csv` data.
+///
+/// Newer versions of Supercell games compress their files using `Zstandard`.
+/// Only the first frame in `raw_data` is decompressed.
+///
+/// ## Arguments
+///
+/// * `raw_data`: `Zstandard` compressed data, starting with the frame's magic bytes.
+fn decompress_zstd(raw_data: &[u8]) -> Result<Cursor<Vec<u8>>> {
+    let mut decoder =
+        StreamingDecoder::new(raw_data).map_err(|e| Error::Decompression(e.to_string()))?;
+
+    let mut decomp: Vec<u8> = Vec::new();
+    match decoder.read_to_end(&mut decomp) {
+        Ok(_) => Ok(Cursor::new(decomp)),
+        Err(e) => Err(Error::Decompression(e.to_string())),
+    }
+}
+
+/// Decompress `LZHAM` compressed `.tex_sc` or `.csv` data.
+///
+/// `LZHAM` data starts with `SCLZ`, followed by the base-2 logarithm of the
+/// dictionary size (one byte) and the decompressed size (little-endian `u32`).
+/// The compressed stream follows.
+///
+/// The header is validated, but decoding the `LZHAM` stream itself is not
+/// supported yet, so `Error::Unsupported` is raised for valid data.
+///
+/// ## Arguments
+///
+/// * `raw_data`: `LZHAM` compressed data, starting with `SCLZ`.
+fn decompress_lzham(raw_data: &[u8]) -> Result<Cursor<Vec<u8>>> {
+    let mut reader = Reader::new(Cursor::new(raw_data.to_vec()));
+    reader.read(LZHAM_MAGIC.len())?;
+
+    let dict_size_log2 = reader.read_byte()?;
+    let size = reader.read_uint32()?;
+
+    // LZHAM supports dictionaries from 2^15 to 2^29 bytes.
+    if !(15..=29).contains(&dict_size_log2) {
+        return Err(Error::InvalidHeader(format!(
+            "invalid LZHAM dictionary size (2^{})",
+            dict_size_log2
+        )));
+    }
+
+    Err(Error::Unsupported(format!(
+        "LZHAM compression (dictionary size 2^{}, {} bytes decompressed)",
+        dict_size_log2, size
+    )))
+}
+
+/// Decodes an image stored as blocks of pixels, such as block-compressed textures.
+///
+/// Blocks are stored row by row, each taking `block_size` bytes. `decode` decodes a
+/// block into its pixels, row by row. Pixels of blocks at the right and bottom edges
+/// which are outside the image are dropped.
+///
+/// If `data` is too short for the image, `Error::UnexpectedEof` is raised before
+/// anything is allocated. If the size of the image overflows, `Error::InvalidData`
+/// is raised.
+///
+/// ## Arguments
+///
+/// * `width`: Width of the image in pixels.
+/// * `height`: Height of the image in pixels.
+/// * `(block_width, block_height)`: Size of the blocks in pixels.
+/// * `block_size`: Size of the blocks in bytes.
+/// * `data`: Block data.
+/// * `decode`: Function decoding a block into its pixels.
+pub(crate) fn decode_blocks<F>(
+    width: u32,
+    height: u32,
+    (block_width, block_height): (u32, u32),
+    block_size: usize,
+    data: &[u8],
+    mut decode: F,
+) -> Result<RgbaImage>
+where
+    F: FnMut(&[u8], &mut [[u8; 4]]),
+{
+    let blocks_x = width.div_ceil(block_width);
+    let blocks_y = height.div_ceil(block_height);
+    let needed = match (blocks_x as usize)
+        .checked_mul(blocks_y as usize)
+        .and_then(|blocks| blocks.checked_mul(block_size))
+    {
+        Some(needed) => needed,
+        None => {
+            return Err(Error::InvalidData(format!(
+                "image of {}x{} pixels is too large",
+                width, height
+            )))
+        }
+    };
+    if data.len() < needed {
+        return Err(Error::UnexpectedEof {
+            offset: data.len(),
+            needed: needed - data.len(),
+        });
     }
+
+    let mut image = RgbaImage::new(width, height);
+    let mut pixels = vec![[0; 4]; (block_width * block_height) as usize];
+    for (i, block) in data
+        .chunks_exact(block_size)
+        .take(needed / block_size)
+        .enumerate()
+    {
+        decode(block, &mut pixels);
+
+        let (block_x, block_y) = (i as u32 % blocks_x, i as u32 / blocks_x);
+        for (j, pixel) in pixels.iter().enumerate() {
+            let x = block_x * block_width + j as u32 % block_width;
+            let y = block_y * block_height + j as u32 / block_width;
+            if x < width && y < height {
+                image.put_pixel(x, y, Rgba(*pixel));
+            }
+        }
+    }
+
+    Ok(image)
 }
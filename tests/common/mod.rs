@@ -0,0 +1,203 @@
+#![allow(dead_code)]
+
+use lzma_rs::compress::{Options, UnpackedSize};
+
+/// Compresses `payload` the way Supercell games do.
+///
+/// The 4 high bytes of the `LZMA` header's unpacked size are removed.
+pub fn compress(payload: &[u8]) -> Vec<u8> {
+    let options = Options {
+        unpacked_size: UnpackedSize::WriteToHeader(Some(payload.len() as u64)),
+    };
+    let mut compressed = Vec::new();
+    lzma_rs::lzma_compress_with_options(&mut &payload[..], &mut compressed, &options).unwrap();
+
+    compressed.drain(9..13);
+    compressed
+}
+
+/// Wraps `payload` in a `Zstandard` frame made of a single raw block.
+pub fn zstd(payload: &[u8]) -> Vec<u8> {
+    let mut frame = vec![0x28, 0xB5, 0x2F, 0xFD];
+    // Single segment, 4-byte frame content size.
+    frame.push(0xA0);
+    frame.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+    // Last block, raw.
+    let header = 1 | ((payload.len() as u32) << 3);
+    frame.extend_from_slice(&header.to_le_bytes()[..3]);
+    frame.extend_from_slice(payload);
+    frame
+}
+
+/// Builds raw `_tex.sc` file data with `payload` as the decompressed content.
+pub fn tex_sc(payload: &[u8]) -> Vec<u8> {
+    let mut data = b"SC".to_vec();
+    data.extend_from_slice(&[0, 0, 0, 1, 0, 0, 0, 16]);
+    data.extend_from_slice(&[0; 16]);
+    data.extend(compress(payload));
+    data
+}
+
+/// Builds a texture block with the given type, sub type, size and pixel data.
+pub fn texture_block(
+    file_type: u8,
+    sub_type: u8,
+    width: u16,
+    height: u16,
+    pixels: &[u8],
+) -> Vec<u8> {
+    let mut block = vec![file_type];
+    block.extend_from_slice(&(5 + pixels.len() as u32).to_le_bytes());
+    block.push(sub_type);
+    block.extend_from_slice(&width.to_le_bytes());
+    block.extend_from_slice(&height.to_le_bytes());
+    block.extend_from_slice(pixels);
+    block
+}
+
+/// Builds a tag with the given type and body.
+pub fn tag(tag: u8, body: &[u8]) -> Vec<u8> {
+    let mut data = vec![tag];
+    data.extend_from_slice(&(body.len() as u32).to_le_bytes());
+    data.extend_from_slice(body);
+    data
+}
+
+/// Builds a length-prefixed string as used by `.sc` object files.
+pub fn ascii(s: &str) -> Vec<u8> {
+    let mut data = vec![s.len() as u8];
+    data.extend_from_slice(s.as_bytes());
+    data
+}
+
+/// Builds the decompressed data of a `.sc` object file with the given exports and tags.
+pub fn objects_payload(exports: &[(u16, &str)], tags: &[Vec<u8>]) -> Vec<u8> {
+    // Shape, movie clip, texture, text field, matrix and color transform counts.
+    let mut payload = vec![0; 12];
+    payload.extend_from_slice(&[0; 5]);
+    payload.extend_from_slice(&(exports.len() as u16).to_le_bytes());
+    for (id, _) in exports {
+        payload.extend_from_slice(&id.to_le_bytes());
+    }
+    for (_, name) in exports {
+        payload.extend(ascii(name));
+    }
+    for t in tags {
+        payload.extend_from_slice(t);
+    }
+    payload.extend(tag(0, &[]));
+    payload
+}
+
+/// Builds a shape tag with a single bitmap of texture `0`.
+pub fn shape(id: u16, xy: &[(i32, i32)], uv: &[(u16, u16)]) -> Vec<u8> {
+    let mut bitmap = vec![0, xy.len() as u8];
+    for (x, y) in xy {
+        bitmap.extend_from_slice(&(x * 20).to_le_bytes());
+        bitmap.extend_from_slice(&(y * 20).to_le_bytes());
+    }
+    for (u, v) in uv {
+        bitmap.extend_from_slice(&u.to_le_bytes());
+        bitmap.extend_from_slice(&v.to_le_bytes());
+    }
+
+    let mut body = id.to_le_bytes().to_vec();
+    body.extend_from_slice(&[1, 0]);
+    body.extend(tag(17, &bitmap));
+    body.extend(tag(0, &[]));
+    tag(2, &body)
+}
+
+pub const RED: [u8; 4] = [255, 0, 0, 255];
+pub const GREEN: [u8; 4] = [0, 255, 0, 255];
+
+/// Builds a `_tex.sc` file with a single red 2x2 texture.
+pub fn red_tex_sc() -> Vec<u8> {
+    tex_sc(&texture_block(1, 0, 2, 2, &RED.repeat(4)))
+}
+
+/// Builds a matrix tag which translates by `(tx, ty)` pixels.
+pub fn translation(tx: i32, ty: i32) -> Vec<u8> {
+    let mut body = Vec::new();
+    for v in &[1024i32, 0, 0, 1024, tx * 20, ty * 20] {
+        body.extend_from_slice(&v.to_le_bytes());
+    }
+    tag(8, &body)
+}
+
+/// Builds an object file with a movie clip which moves a red square to the
+/// right and turns it green in its second frame.
+pub fn animated_sc() -> Vec<u8> {
+    let square = [(0, 0), (2, 0), (2, 2), (0, 2)];
+    let uv = [(0, 0), (2, 0), (2, 2), (0, 2)];
+
+    // Id 5, 10 fps, 2 frames.
+    let mut clip = vec![5, 0, 10, 2, 0];
+    clip.extend_from_slice(&2u32.to_le_bytes());
+    clip.extend_from_slice(&[0, 0, 0, 0, 0xFF, 0xFF]);
+    clip.extend_from_slice(&[0, 0, 1, 0, 0, 0]);
+    // A single, unnamed child.
+    clip.extend_from_slice(&[1, 0, 1, 0, 0xFF]);
+    clip.extend(tag(11, &[1, 0, 0xFF]));
+    clip.extend(tag(11, &[1, 0, 0xFF]));
+    clip.extend(tag(0, &[]));
+
+    let payload = objects_payload(
+        &[(5, "walk")],
+        &[
+            tag(1, &[0, 2, 0, 2, 0]),
+            shape(1, &square, &uv),
+            tag(10, &clip),
+            translation(0, 0),
+            translation(2, 0),
+            // Removes red and adds green.
+            tag(9, &[0, 255, 0, 255, 0, 255, 255]),
+        ],
+    );
+    tex_sc(&payload)
+}
+
+/// Builds a `KTX` image with a single mipmap level.
+pub fn ktx(
+    gl_type: u32,
+    gl_format: u32,
+    gl_internal_format: u32,
+    width: u32,
+    height: u32,
+    level: &[u8],
+) -> Vec<u8> {
+    let mut data = vec![
+        0xAB, 0x4B, 0x54, 0x58, 0x20, 0x31, 0x31, 0xBB, 0x0D, 0x0A, 0x1A, 0x0A,
+    ];
+    let type_size = if gl_type == 0x1401 { 1 } else { 2 };
+    for v in &[
+        0x0403_0201,
+        gl_type,
+        type_size,
+        gl_format,
+        gl_internal_format,
+        gl_format,
+        width,
+        height,
+        0,
+        0,
+        1,
+        1,
+        0,
+    ] {
+        data.extend_from_slice(&v.to_le_bytes());
+    }
+    data.extend_from_slice(&(level.len() as u32).to_le_bytes());
+    data.extend_from_slice(level);
+    data
+}
+
+/// Builds a texture block of type `45`, embedding a `KTX` image.
+pub fn ktx_block(sub_type: u8, width: u16, height: u16, ktx: &[u8]) -> Vec<u8> {
+    let mut body = (ktx.len() as u32).to_le_bytes().to_vec();
+    body.push(sub_type);
+    body.extend_from_slice(&width.to_le_bytes());
+    body.extend_from_slice(&height.to_le_bytes());
+    body.extend_from_slice(ktx);
+    tag(45, &body)
+}
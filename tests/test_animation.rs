@@ -0,0 +1,114 @@
+mod common;
+
+use common::{animated_sc, red_tex_sc, GREEN, RED};
+use image::{gif::GifDecoder, AnimationDecoder};
+use sc_extract::{
+    decode_animations, decode_objects, decode_tex, process_animations, AnimationFormat,
+    RenderOptions,
+};
+use std::{fs, path::Path};
+
+#[test]
+fn test_render() {
+    let animations = decode_animations(
+        &animated_sc(),
+        &red_tex_sc(),
+        None,
+        &RenderOptions::default(),
+    )
+    .unwrap();
+    assert_eq!(1, animations.len());
+
+    let animation = &animations[0];
+    assert_eq!(Some("walk"), animation.name.as_deref());
+    assert_eq!(10, animation.fps);
+    assert_eq!(2, animation.frames.len());
+
+    let first = &animation.frames[0];
+    assert_eq!((4, 2), first.dimensions());
+    assert_eq!(RED, first.get_pixel(1, 1).0);
+    assert_eq!([0; 4], first.get_pixel(3, 1).0);
+
+    let second = &animation.frames[1];
+    assert_eq!([0; 4], second.get_pixel(1, 1).0);
+    assert_eq!(GREEN, second.get_pixel(3, 1).0);
+}
+
+#[test]
+fn test_options() {
+    let options = RenderOptions {
+        scale: 2.0,
+        fps: Some(30),
+    };
+    let animations = decode_animations(&animated_sc(), &red_tex_sc(), None, &options).unwrap();
+
+    assert_eq!(30, animations[0].fps);
+    assert_eq!((8, 4), animations[0].frames[0].dimensions());
+}
+
+#[test]
+fn test_encode() {
+    let animations = decode_animations(
+        &animated_sc(),
+        &red_tex_sc(),
+        None,
+        &RenderOptions::default(),
+    )
+    .unwrap();
+
+    let mut gif = Vec::new();
+    animations[0]
+        .encode(&mut gif, AnimationFormat::Gif)
+        .unwrap();
+    let frames = GifDecoder::new(&gif[..])
+        .unwrap()
+        .into_frames()
+        .collect_frames()
+        .unwrap();
+    assert_eq!(2, frames.len());
+
+    let mut apng = Vec::new();
+    animations[0]
+        .encode(&mut apng, AnimationFormat::Apng)
+        .unwrap();
+    let reader = png::Decoder::new(&apng[..]).read_info().unwrap();
+    let control = reader.info().animation_control.unwrap();
+    assert_eq!(2, control.num_frames);
+    assert_eq!(0, control.num_plays);
+}
+
+#[test]
+fn test_process() {
+    let objects = decode_objects(&animated_sc()).unwrap();
+    let textures: Vec<_> = decode_tex(&red_tex_sc())
+        .unwrap()
+        .into_iter()
+        .map(Some)
+        .collect();
+    let out_dir = std::env::temp_dir().join(format!("sc_extract_anim_{}", std::process::id()));
+    let path = Path::new("ui.sc");
+    let options = RenderOptions::default();
+
+    for &format in &[AnimationFormat::Gif, AnimationFormat::Apng] {
+        process_animations(&objects, &textures, path, &out_dir, format, &options, true).unwrap();
+    }
+    let gif = fs::read(out_dir.join("ui/walk_anim.gif")).unwrap();
+    let apng = fs::read(out_dir.join("ui/walk_anim.png")).unwrap();
+    fs::remove_dir_all(&out_dir).unwrap();
+
+    let frames = GifDecoder::new(&gif[..])
+        .unwrap()
+        .into_frames()
+        .collect_frames()
+        .unwrap();
+    assert_eq!(2, frames.len());
+    assert_eq!((4, 2), frames[0].buffer().dimensions());
+
+    let mut reader = png::Decoder::new(&apng[..]).read_info().unwrap();
+    assert_eq!(2, reader.info().animation_control.unwrap().num_frames);
+    let mut frame = vec![0; reader.output_buffer_size()];
+    reader.next_frame(&mut frame).unwrap();
+    reader.next_frame(&mut frame).unwrap();
+    // The second frame has green at (3, 1).
+    assert_eq!(GREEN, frame[(4 + 3) * 4..(4 + 4) * 4]);
+}
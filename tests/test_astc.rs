@@ -0,0 +1,111 @@
+mod common;
+
+use common::ktx;
+use image::RgbaImage;
+use sc_extract::{decode_ktx, Error};
+
+const GL_COMPRESSED_RGBA_ASTC_4X4: u32 = 0x93B0;
+const GL_COMPRESSED_RGBA_ASTC_8X8: u32 = 0x93B7;
+const GL_COMPRESSED_SRGB8_ALPHA8_ASTC_6X6: u32 = 0x93D4;
+
+/// A void-extent block, filled with a single color of 16-bit channels.
+fn void_extent(color: [u16; 4]) -> [u8; 16] {
+    // No extent coordinates, all ones, and the `LDR` flag cleared.
+    let mut bits: u128 = 0xFFFF_FFFF_FFFF_FDFC;
+    for (i, c) in color.iter().enumerate() {
+        bits |= (*c as u128) << (64 + 16 * i);
+    }
+    bits.to_le_bytes()
+}
+
+/// A 4x4 luminance block with a 4x4 grid of 2-bit weights, going from `0` on the
+/// left to `3` on the right.
+fn luminance_block(l0: u8, l1: u8) -> [u8; 16] {
+    // Weights in range 4 (2 bits), grid of 4x4, a single partition of mode 0.
+    let mut bits: u128 = 0x042 | (l0 as u128) << 17 | (l1 as u128) << 25;
+    for i in 0..16 {
+        let weight = i % 4;
+        bits |= (weight & 1) << (127 - 2 * i);
+        bits |= (weight >> 1) << (126 - 2 * i);
+    }
+    bits.to_le_bytes()
+}
+
+fn decode(internal_format: u32, width: u32, height: u32, blocks: &[u8]) -> RgbaImage {
+    decode_ktx(&ktx(0, 0, internal_format, width, height, blocks)).unwrap()
+}
+
+#[test]
+fn test_void_extent() {
+    let block = void_extent([0xFFFF, 0x8000, 0, 0xFFFF]);
+    let image = decode(GL_COMPRESSED_RGBA_ASTC_4X4, 4, 4, &block);
+    assert!(image.pixels().all(|p| p.0 == [255, 128, 0, 255]));
+
+    // Blocks at the edges are cropped to the image.
+    let blocks = [block, void_extent([0, 0, 0xFFFF, 0x4000])].concat();
+    let image = decode(GL_COMPRESSED_RGBA_ASTC_8X8, 12, 6, &blocks);
+    assert_eq!((12, 6), image.dimensions());
+    assert_eq!([255, 128, 0, 255], image.get_pixel(7, 5).0);
+    assert_eq!([0, 0, 255, 64], image.get_pixel(11, 0).0);
+
+    match decode_ktx(&ktx(
+        0,
+        0,
+        GL_COMPRESSED_SRGB8_ALPHA8_ASTC_6X6,
+        8,
+        6,
+        &block,
+    )) {
+        Err(Error::UnexpectedEof { needed: 16, .. }) => (),
+        r => panic!("unexpected result: {:?}", r.map(|i| i.dimensions())),
+    }
+}
+
+#[test]
+fn test_weights() {
+    let image = decode(GL_COMPRESSED_RGBA_ASTC_4X4, 4, 4, &luminance_block(0, 255));
+    for (x, l) in [0, 84, 171, 255].iter().enumerate() {
+        assert_eq!([*l, *l, *l, 255], image.get_pixel(x as u32, 3).0);
+    }
+
+    // Weights are stretched over larger blocks.
+    let image = decode(
+        GL_COMPRESSED_SRGB8_ALPHA8_ASTC_6X6,
+        6,
+        6,
+        &luminance_block(0, 255),
+    );
+    assert_eq!([0, 0, 0, 255], image.get_pixel(0, 0).0);
+    assert_eq!([255, 255, 255, 255], image.get_pixel(5, 5).0);
+    assert!(image.get_pixel(2, 0).0[0] > image.get_pixel(1, 0).0[0]);
+}
+
+#[test]
+fn test_error_blocks() {
+    // Reserved block modes are decoded in magenta.
+    let image = decode(GL_COMPRESSED_RGBA_ASTC_4X4, 4, 4, &[0; 16]);
+    assert!(image.pixels().all(|p| p.0 == [255, 0, 255, 255]));
+}
+
+#[test]
+fn test_invalid_sizes() {
+    // The block count overflows and is not mistaken for a small image.
+    let data = ktx(
+        0,
+        0,
+        GL_COMPRESSED_RGBA_ASTC_4X4,
+        u32::MAX,
+        u32::MAX,
+        &[0; 16],
+    );
+    match decode_ktx(&data) {
+        Err(Error::InvalidData(_)) => (),
+        _ => panic!("expected `Error::InvalidData`"),
+    }
+
+    let data = ktx(0, 0, GL_COMPRESSED_RGBA_ASTC_4X4, 65536, 65536, &[0; 16]);
+    match decode_ktx(&data) {
+        Err(Error::UnexpectedEof { offset: 16, .. }) => (),
+        _ => panic!("expected `Error::UnexpectedEof`"),
+    }
+}
@@ -0,0 +1,178 @@
+mod common;
+
+use common::{tex_sc, texture_block};
+use image::{imageops, RgbaImage};
+use sc_extract::{decode_tex, PixelFormat};
+
+/// Sub-types of the formats with less than 8 bits per channel.
+const RGBA4444: u8 = 2;
+const RGBA5551: u8 = 3;
+const RGB565: u8 = 4;
+const LA44: u8 = 5;
+
+/// Reads the first texture of a fixture, cropped to its top left corner.
+fn fixture(name: &str) -> RgbaImage {
+    let data = std::fs::read(format!("./tests/data/sc/{}", name)).unwrap();
+    let image = &decode_tex(&data).unwrap()[0].image;
+    imageops::crop_imm(image, 0, 0, 256, 256).to_image()
+}
+
+/// Encodes an image in a format with less than 8 bits per channel, keeping the
+/// high bits of each channel, and decodes it back from a `_tex.sc` file.
+fn round_trip(image: &RgbaImage, sub_type: u8) -> RgbaImage {
+    let mut pixels = Vec::new();
+    for p in image.pixels() {
+        let [r, g, b, a] = p.0.map(u16::from);
+        let value = match sub_type {
+            RGBA4444 => (r >> 4) << 12 | (g >> 4) << 8 | (b >> 4) << 4 | a >> 4,
+            RGBA5551 => (r >> 3) << 11 | (g >> 3) << 6 | (b >> 3) << 1 | a >> 7,
+            _ => (r >> 3) << 11 | (g >> 2) << 5 | b >> 3,
+        };
+        pixels.extend_from_slice(&value.to_le_bytes());
+    }
+
+    let (width, height) = (image.width() as u16, image.height() as u16);
+    let data = tex_sc(&texture_block(1, sub_type, width, height, &pixels));
+    decode_tex(&data).unwrap().remove(0).image
+}
+
+/// Checks that each channel is within the precision of its bits, and that full
+/// intensity and opaque pixels are kept.
+fn check(source: &RgbaImage, decoded: &RgbaImage, bits: [u32; 4]) {
+    for (s, d) in source.pixels().zip(decoded.pixels()) {
+        for ((&source, &decoded), &bits) in s.0.iter().zip(&d.0).zip(&bits) {
+            if bits == 0 {
+                assert_eq!(255, decoded);
+                continue;
+            }
+            let tolerance = (1 << (8 - bits)) - 1;
+            assert!((source as i32 - decoded as i32).abs() <= tolerance);
+            if source == 255 {
+                assert_eq!(255, decoded, "{:?} decoded as {:?}", s.0, d.0);
+            }
+        }
+    }
+}
+
+/// Decodes a row of pixels stored in a `_tex.sc` file.
+fn decode_row(sub_type: u8, width: u16, pixels: &[u8]) -> Vec<[u8; 4]> {
+    let data = tex_sc(&texture_block(1, sub_type, width, 1, pixels));
+    let image = decode_tex(&data).unwrap().remove(0).image;
+    image.pixels().map(|p| p.0).collect()
+}
+
+/// Decodes 16-bit pixels stored in a `_tex.sc` file.
+fn decode_shorts(sub_type: u8, values: &[u16]) -> Vec<[u8; 4]> {
+    let pixels: Vec<u8> = values.iter().flat_map(|v| v.to_le_bytes()).collect();
+    decode_row(sub_type, values.len() as u16, &pixels)
+}
+
+#[test]
+fn test_expansion() {
+    // Full intensity stays full intensity and zero stays zero.
+    assert_eq!(
+        [255; 4],
+        PixelFormat::Rgba4444.decode(&[0xFF, 0xFF]).unwrap()
+    );
+    assert_eq!([0, 0, 0, 0], PixelFormat::Rgba4444.decode(&[0, 0]).unwrap());
+    assert_eq!(
+        [255; 4],
+        PixelFormat::Rgba5551.decode(&[0xFF, 0xFF]).unwrap()
+    );
+    assert_eq!([255; 4], PixelFormat::Rgb565.decode(&[0xFF, 0xFF]).unwrap());
+    assert_eq!([0, 0, 0, 255], PixelFormat::Rgb565.decode(&[0, 0]).unwrap());
+
+    // The high bits are repeated in the low bits.
+    assert_eq!(
+        [0x88, 0x44, 0xCC, 0x11],
+        PixelFormat::Rgba4444
+            .decode(&0x84C1u16.to_le_bytes())
+            .unwrap()
+    );
+    assert_eq!(
+        [0x84, 0x82, 0x84, 255],
+        PixelFormat::Rgb565
+            .decode(&0x8410u16.to_le_bytes())
+            .unwrap()
+    );
+}
+
+#[test]
+fn test_edge_values() {
+    // Channels of 4 bits are repeated twice.
+    assert_eq!(
+        vec![
+            [0x00, 0x00, 0x00, 0x00],
+            [0xFF, 0xFF, 0xFF, 0xFF],
+            [0x11, 0x22, 0x33, 0x44],
+            [0xFF, 0x00, 0x00, 0xFF],
+            [0x88, 0x00, 0x00, 0x00],
+            [0x77, 0xFF, 0xFF, 0xFF],
+        ],
+        decode_shorts(RGBA4444, &[0x0000, 0xFFFF, 0x1234, 0xF00F, 0x8000, 0x7FFF])
+    );
+
+    // Channels of 5 bits keep their 3 high bits in the low bits, and alpha is
+    // either transparent or opaque.
+    assert_eq!(
+        vec![
+            [0x00, 0x00, 0x00, 0x00],
+            [0xFF, 0xFF, 0xFF, 0xFF],
+            [0x00, 0x00, 0x00, 0xFF],
+            [0xFF, 0x00, 0x00, 0x00],
+            [0x08, 0x00, 0x00, 0x00],
+            [0x84, 0x00, 0x00, 0x00],
+            [0x00, 0x7B, 0x00, 0x00],
+            [0x00, 0x00, 0xFF, 0x00],
+        ],
+        decode_shorts(
+            RGBA5551,
+            &[0x0000, 0xFFFF, 0x0001, 0xF800, 0x0800, 0x8000, 0x03C0, 0x003E]
+        )
+    );
+
+    // Green has 6 bits and keeps its 2 high bits in the low bits. Pixels are opaque.
+    assert_eq!(
+        vec![
+            [0x00, 0x00, 0x00, 0xFF],
+            [0xFF, 0xFF, 0xFF, 0xFF],
+            [0x00, 0xFF, 0x00, 0xFF],
+            [0x00, 0x04, 0x00, 0xFF],
+            [0x00, 0x82, 0x00, 0xFF],
+            [0x08, 0x00, 0x00, 0xFF],
+            [0x00, 0x00, 0xFF, 0xFF],
+        ],
+        decode_shorts(
+            RGB565,
+            &[0x0000, 0xFFFF, 0x07E0, 0x0020, 0x0400, 0x0800, 0x001F]
+        )
+    );
+
+    // Luminance in the high 4 bits and alpha in the low 4 bits of a single byte.
+    assert_eq!(
+        vec![
+            [0x00, 0x00, 0x00, 0x00],
+            [0xFF, 0xFF, 0xFF, 0xFF],
+            [0xFF, 0xFF, 0xFF, 0x00],
+            [0x00, 0x00, 0x00, 0xFF],
+            [0x88, 0x88, 0x88, 0x44],
+            [0x11, 0x11, 0x11, 0xEE],
+        ],
+        decode_row(LA44, 6, &[0x00, 0xFF, 0xF0, 0x0F, 0x84, 0x1E])
+    );
+}
+
+#[test]
+fn test_fixtures() {
+    // Fixtures only store 8-bit textures, so they are converted to the other formats.
+    for name in &["background_scity_tex.sc", "characters_tex.sc"] {
+        let source = fixture(name);
+        let rgba4444 = round_trip(&source, RGBA4444);
+        let rgba5551 = round_trip(&source, RGBA5551);
+        let rgb565 = round_trip(&source, RGB565);
+
+        check(&source, &rgba4444, [4, 4, 4, 4]);
+        check(&source, &rgba5551, [5, 5, 5, 1]);
+        check(&source, &rgb565, [5, 6, 5, 0]);
+    }
+}
@@ -0,0 +1,110 @@
+mod common;
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    process::{Command, Output},
+};
+
+/// Contents of the `.csv` files of the tests.
+const CSV: &[u8] = b"\"Name\",\"Level\"\n\"String\",\"int\"\n";
+
+/// Creates an empty directory for a test.
+fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("sc_extract_cli_{}_{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+/// Runs the command-line tool on `path`, saving extracts in `dir/out`.
+fn run(path: &Path, dir: &Path, args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_sc_extract"))
+        .arg(path)
+        .arg("-o")
+        .arg(dir.join("out"))
+        .args(args)
+        .output()
+        .unwrap()
+}
+
+/// Returns a `_tex.sc` fixture whose hash doesn't match its data.
+fn mismatching_tex() -> Vec<u8> {
+    let mut data = fs::read("./tests/data/sc/background_scity_tex.sc").unwrap();
+    data[10] ^= 0xFF;
+    data
+}
+
+#[test]
+fn test_verify_without_hash() {
+    // Compressed `.csv` files have no `SC` header, so they are extracted as usual.
+    let dir = temp_dir("no_hash");
+    let path = dir.join("levels.csv");
+    fs::write(&path, common::zstd(CSV)).unwrap();
+
+    let output = run(&path, &dir, &["-c"]);
+    let extracted = fs::read(dir.join("out/extracts/levels.csv"));
+    fs::remove_dir_all(&dir).unwrap();
+
+    assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout).contains("No hash to verify"));
+    assert_eq!(CSV, extracted.unwrap().as_slice());
+}
+
+#[test]
+fn test_verify_mismatch() {
+    let dir = temp_dir("mismatch");
+    let path = dir.join("background_tex.sc");
+    fs::write(&path, mismatching_tex()).unwrap();
+
+    let output = run(&path, &dir, &["-c"]);
+    let extracted = dir.join("out/extracts/background_tex.png").exists();
+
+    // Other files of a directory are still extracted, but the run fails.
+    fs::write(dir.join("levels.csv"), common::zstd(CSV)).unwrap();
+    let dir_output = run(&dir, &dir, &["-c"]);
+    let csv_extracted = dir.join("out/extracts/levels.csv").exists();
+    fs::remove_dir_all(&dir).unwrap();
+
+    assert!(!output.status.success());
+    assert!(!extracted);
+    assert!(!dir_output.status.success());
+    assert!(csv_extracted);
+}
+
+#[test]
+fn test_delete_after_all_files() {
+    // `ui.sc` needs `ui_tex.sc`, whichever of them is processed first.
+    for args in &[&["-s", "-d"][..], &["-s", "-d", "-p"]] {
+        let dir = temp_dir("delete");
+        fs::write(dir.join("ui.sc"), common::animated_sc()).unwrap();
+        fs::write(dir.join("ui_tex.sc"), common::red_tex_sc()).unwrap();
+
+        let output = run(&dir, &dir, args);
+        let sprites = fs::read_dir(dir.join("out/extracts/ui")).map(|d| d.count());
+        let deleted = !dir.join("ui.sc").exists() && !dir.join("ui_tex.sc").exists();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert!(output.status.success());
+        assert!(sprites.unwrap() > 0);
+        assert!(deleted);
+    }
+}
+
+#[test]
+fn test_invalid_scale() {
+    let dir = temp_dir("scale");
+    let outputs: Vec<_> = ["--scale=0", "--scale=-1", "--scale=NaN", "--scale=inf"]
+        .iter()
+        .map(|arg| run(&dir, &dir, &["-a", "gif", arg]))
+        .collect();
+    let valid = run(&dir, &dir, &["-a", "gif", "--scale=0.5"]);
+    fs::remove_dir_all(&dir).unwrap();
+
+    for output in outputs {
+        assert!(!output.status.success());
+        assert!(String::from_utf8_lossy(&output.stderr).contains("scale"));
+    }
+    // The directory is empty, but the scale is accepted.
+    assert!(!String::from_utf8_lossy(&valid.stderr).contains("scale"));
+}
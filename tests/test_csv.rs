@@ -1,13 +1,15 @@
+mod common;
+
 use rayon::prelude::*;
-use sc_extract::process_csv;
+use sc_extract::{decode_csv, decode_csv_records, process_csv, Error};
 use std::{fs, path::Path};
 
 #[test]
 fn test_single() {
     let path = Path::new("./tests/data/csv/alliance_badges.csv");
-    let data = fs::read(&path).unwrap();
+    let data = fs::read(path).unwrap();
     let out_dir = Path::new("./tests/out/csv");
-    assert_eq!(true, process_csv(data.as_slice(), &path, &out_dir, true).is_ok());
+    assert!(process_csv(data.as_slice(), path, out_dir, true).is_ok());
 }
 
 #[test]
@@ -16,7 +18,7 @@ fn test_all_parallel() {
     let out_dir = Path::new("./tests/out/csv");
     let dir_entries = match fs::read_dir(dir) {
         Ok(e) => e,
-        Err(_) => std::process::exit(1)
+        Err(_) => std::process::exit(1),
     };
 
     let mut entries = Vec::new();
@@ -27,7 +29,7 @@ fn test_all_parallel() {
     entries.into_par_iter().for_each(|entry| {
         let path = entry.unwrap().path();
         let data = fs::read(&path).unwrap();
-        assert_eq!(true, process_csv(data.as_slice(), &path, &out_dir, true).is_ok());
+        assert!(process_csv(data.as_slice(), &path, out_dir, true).is_ok());
     });
 }
 
@@ -37,7 +39,7 @@ fn test_all_blocking() {
     let out_dir = Path::new("./tests/out/csv");
     let dir_entries = match fs::read_dir(dir) {
         Ok(e) => e,
-        Err(_) => std::process::exit(1)
+        Err(_) => std::process::exit(1),
     };
 
     let mut entries = Vec::new();
@@ -48,6 +50,66 @@ fn test_all_blocking() {
     for entry in entries {
         let path = entry.unwrap().path();
         let data = fs::read(&path).unwrap();
-        assert_eq!(true, process_csv(data.as_slice(), &path, &out_dir, false).is_ok());
+        assert!(process_csv(data.as_slice(), &path, out_dir, false).is_ok());
+    }
+}
+
+#[test]
+fn test_decode() {
+    let path = Path::new("./tests/data/csv/alliance_roles.csv");
+    let data = fs::read(path).unwrap();
+    let decoded = decode_csv(data.as_slice()).unwrap();
+    let expected = fs::read("./tests/out/csv/alliance_roles.csv").unwrap();
+    assert_eq!(expected, decoded);
+}
+
+#[test]
+fn test_decode_records() {
+    let path = Path::new("./tests/data/csv/alliance_roles.csv");
+    let data = fs::read(path).unwrap();
+    let records = decode_csv_records(data.as_slice()).unwrap();
+
+    assert_eq!(7, records.len());
+    assert_eq!("Name", records[0][0]);
+    assert_eq!("String", records[1][0]);
+    assert_eq!("NonMember", records[2][0]);
+}
+
+#[test]
+fn test_decode_zstd() {
+    let csv = b"\"Name\",\"Level\"\n\"String\",\"int\"\n\"Leader\",4\n";
+    let data = common::zstd(csv);
+    assert_eq!(csv.to_vec(), decode_csv(&data).unwrap());
+    assert_eq!("Leader", decode_csv_records(&data).unwrap()[2][0]);
+}
+
+#[test]
+fn test_decode_lzham() {
+    let mut data = b"SCLZ".to_vec();
+    data.push(18);
+    data.extend_from_slice(&100u32.to_le_bytes());
+    data.extend_from_slice(&[0; 16]);
+
+    match decode_csv(&data) {
+        Err(Error::Unsupported(_)) => (),
+        _ => panic!("expected `Error::Unsupported`"),
     }
+
+    data[4] = 40;
+    match decode_csv(&data) {
+        Err(Error::InvalidHeader(_)) => (),
+        _ => panic!("expected `Error::InvalidHeader`"),
+    }
+}
+
+#[test]
+fn test_decode_signed() {
+    let csv = fs::read("./tests/out/csv/alliance_roles.csv").unwrap();
+    let mut data = b"Sig:".to_vec();
+    data.extend_from_slice(&[7; 64]);
+    data.extend_from_slice(b"SC");
+    data.extend_from_slice(&[0, 0, 0, 3, 0, 0, 0, 0]);
+    data.extend(common::zstd(&csv));
+
+    assert_eq!(csv, decode_csv(&data).unwrap());
 }
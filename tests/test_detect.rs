@@ -0,0 +1,93 @@
+mod common;
+
+use sc_extract::{detect, detect_file, Compression, FileKind};
+use std::fs;
+
+#[test]
+fn test_sc_texture() {
+    for entry in fs::read_dir("./tests/data/sc").unwrap() {
+        let data = fs::read(entry.unwrap().path()).unwrap();
+        assert_eq!(FileKind::ScTexture, detect(&data));
+    }
+}
+
+#[test]
+fn test_sc_object() {
+    // Shape, movie clip, texture, text field, matrix and color transform counts.
+    let mut payload = vec![2, 0, 1, 0, 1, 0, 0, 0, 3, 0, 0, 0];
+    payload.extend_from_slice(&[0; 5]);
+    payload.extend_from_slice(&[0, 0]);
+    assert_eq!(FileKind::ScObject, detect(&common::tex_sc(&payload)));
+}
+
+#[test]
+fn test_detect_file() {
+    let payload = [1, 0, 0, 0, 0, 0, 0, 0, 0, 0];
+    let detected = detect_file(&common::tex_sc(&payload));
+    assert_eq!(FileKind::ScTexture, detected.kind);
+    assert_eq!(Some(&payload[..]), detected.decompressed.as_deref());
+    assert_eq!(16, detected.header.unwrap().hash.len());
+
+    let csv = b"\"Name\",\"Level\"\n\"String\",\"int\"\n";
+    let detected = detect_file(&common::zstd(csv));
+    assert_eq!(FileKind::CompressedCsv, detected.kind);
+    assert!(detected.header.is_none());
+    assert_eq!(Some(&csv[..]), detected.decompressed.as_deref());
+
+    let detected = detect_file(b"\"Name\",\"Level\"\n");
+    assert_eq!(FileKind::Csv, detected.kind);
+    assert!(detected.decompressed.is_none());
+}
+
+#[test]
+fn test_csv() {
+    for entry in fs::read_dir("./tests/data/csv").unwrap() {
+        let data = fs::read(entry.unwrap().path()).unwrap();
+        assert_eq!(FileKind::CompressedCsv, detect(&data));
+    }
+
+    for entry in fs::read_dir("./tests/out/csv").unwrap() {
+        let data = fs::read(entry.unwrap().path()).unwrap();
+        assert_eq!(FileKind::Csv, detect(&data));
+    }
+
+    let csv = b"\"Name\",\"Level\"\n\"String\",\"int\"\n";
+    assert_eq!(FileKind::CompressedCsv, detect(&common::zstd(csv)));
+}
+
+#[test]
+fn test_compressed() {
+    let binary = [0, 1, 2, 3, 255, 254, 253];
+    assert_eq!(
+        FileKind::Compressed(Compression::Zstd),
+        detect(&common::zstd(&binary))
+    );
+    assert_eq!(
+        FileKind::Compressed(Compression::Lzma),
+        detect(&common::compress(&binary))
+    );
+
+    let mut lzham = b"SCLZ".to_vec();
+    lzham.push(18);
+    lzham.extend_from_slice(&[0; 8]);
+    assert_eq!(FileKind::Compressed(Compression::Lzham), detect(&lzham));
+}
+
+#[test]
+fn test_images() {
+    let mut ktx = vec![
+        0xAB, 0x4B, 0x54, 0x58, 0x20, 0x31, 0x31, 0xBB, 0x0D, 0x0A, 0x1A, 0x0A,
+    ];
+    ktx.extend_from_slice(&[0; 52]);
+    assert_eq!(FileKind::Ktx, detect(&ktx));
+
+    let mut pvr = b"PVR\x03".to_vec();
+    pvr.extend_from_slice(&[0; 48]);
+    assert_eq!(FileKind::Pvr, detect(&pvr));
+}
+
+#[test]
+fn test_unknown() {
+    assert_eq!(FileKind::Unknown, detect(&[]));
+    assert_eq!(FileKind::Unknown, detect(&[1, 2, 3, 4, 5, 6, 7, 8, 9, 10]));
+}
@@ -0,0 +1,76 @@
+mod common;
+
+use common::{shape, tag};
+use sc_extract::dump_sc;
+use std::fs;
+
+#[test]
+fn test_textures() {
+    let data = fs::read("./tests/data/sc/background_basic_tex.sc").unwrap();
+    let dump = dump_sc(&data).unwrap();
+
+    assert_eq!("textures", dump.kind);
+    assert_eq!(1, dump.header.version);
+    assert_eq!(32, dump.header.hash.len());
+    assert_eq!(2, dump.tags.len());
+    assert!(dump.error.is_none());
+
+    let texture = &dump.tags[0];
+    assert_eq!(
+        (1, "texture", 0),
+        (texture.tag, texture.name, texture.offset)
+    );
+    let fields = texture.fields.as_ref().unwrap();
+    assert_eq!(1024, fields["width"]);
+    assert_eq!(0, fields["sub_type"]);
+    assert_eq!(5 + texture.length, dump.tags[1].offset);
+}
+
+#[test]
+fn test_objects() {
+    let payload = common::objects_payload(
+        &[(1, "icon")],
+        &[
+            shape(1, &[(0, 0), (1, 0), (1, 1)], &[(0, 0), (1, 0), (1, 1)]),
+            tag(99, &[0xAB, 0xCD]),
+            tag(9, &[1, 2, 3]),
+            tag(32, &[1, b'x', 1, b'y']),
+            tag(23, &[]),
+        ],
+    );
+    let dump = dump_sc(&common::tex_sc(&payload)).unwrap();
+
+    assert_eq!("objects", dump.kind);
+    assert_eq!("icon", dump.objects.as_ref().unwrap()["exports"][0]["name"]);
+    assert_eq!(5, dump.tags.len());
+
+    let shape = &dump.tags[0];
+    assert_eq!("shape", shape.name);
+    assert_eq!(
+        1,
+        shape.fields.as_ref().unwrap()["bitmaps"]
+            .as_array()
+            .unwrap()
+            .len()
+    );
+
+    let unknown = &dump.tags[1];
+    assert_eq!("unknown", unknown.name);
+    assert_eq!(Some("abcd"), unknown.data.as_deref());
+
+    // The color transform is too short to be decoded.
+    let damaged = &dump.tags[2];
+    assert_eq!("color_transform", damaged.name);
+    assert!(damaged.fields.is_none());
+    assert!(damaged.error.is_some());
+    assert_eq!(Some("010203"), damaged.data.as_deref());
+
+    // Known tags without decoded fields are kept as hex too.
+    let postfixes = &dump.tags[3];
+    assert_eq!("texture_postfixes", postfixes.name);
+    assert_eq!(Some("01780179"), postfixes.data.as_deref());
+    assert_eq!(Some(""), dump.tags[4].data.as_deref());
+    assert!(shape.data.is_none());
+
+    assert!(dump.to_json().unwrap().contains("\"exports\""));
+}
@@ -0,0 +1,107 @@
+mod common;
+
+use common::ktx;
+use image::RgbaImage;
+use sc_extract::{decode_ktx, Error};
+
+const GL_ETC1_RGB8: u32 = 0x8D64;
+const GL_COMPRESSED_R11_EAC: u32 = 0x9270;
+const GL_COMPRESSED_RGB8_ETC2: u32 = 0x9274;
+const GL_COMPRESSED_RGB8_PUNCHTHROUGH_ALPHA1_ETC2: u32 = 0x9276;
+const GL_COMPRESSED_RGBA8_ETC2_EAC: u32 = 0x9278;
+
+/// An `ETC1` block in individual mode, red on the left and green on the right.
+/// The top left pixel uses the largest negative modifier.
+const SPLIT_BLOCK: [u8; 8] = [0xF0, 0x0F, 0x00, 0x00, 0x00, 0x01, 0x00, 0x01];
+
+fn decode(internal_format: u32, width: u32, height: u32, blocks: &[u8]) -> RgbaImage {
+    decode_ktx(&ktx(0, 0, internal_format, width, height, blocks)).unwrap()
+}
+
+#[test]
+fn test_etc1() {
+    let image = decode(GL_ETC1_RGB8, 4, 4, &SPLIT_BLOCK);
+    assert_eq!([247, 0, 0, 255], image.get_pixel(0, 0).0);
+    assert_eq!([255, 2, 2, 255], image.get_pixel(1, 3).0);
+    assert_eq!([2, 255, 2, 255], image.get_pixel(2, 0).0);
+
+    // Flipped blocks are split into top and bottom halves.
+    let mut flipped = SPLIT_BLOCK;
+    flipped[3] |= 1;
+    let image = decode(GL_ETC1_RGB8, 4, 4, &flipped);
+    assert_eq!([255, 2, 2, 255], image.get_pixel(3, 1).0);
+    assert_eq!([2, 255, 2, 255], image.get_pixel(0, 2).0);
+
+    // Blocks at the edges are cropped to the image.
+    let image = decode(GL_ETC1_RGB8, 6, 3, &[SPLIT_BLOCK, SPLIT_BLOCK].concat());
+    assert_eq!((6, 3), image.dimensions());
+    assert_eq!([255, 2, 2, 255], image.get_pixel(5, 2).0);
+
+    match decode_ktx(&ktx(0, 0, GL_ETC1_RGB8, 8, 4, &SPLIT_BLOCK)) {
+        Err(Error::UnexpectedEof { needed: 8, .. }) => (),
+        r => panic!("unexpected result: {:?}", r.map(|i| i.dimensions())),
+    }
+}
+
+#[test]
+fn test_etc2_modes() {
+    // T mode: green and three shades of gray, one per pixel of the first row.
+    let t_mode = [0x04, 0xF0, 0x88, 0x82, 0x11, 0x00, 0x10, 0x10];
+    let image = decode(GL_COMPRESSED_RGB8_ETC2, 4, 4, &t_mode);
+    assert_eq!([0, 255, 0, 255], image.get_pixel(0, 0).0);
+    assert_eq!([139, 139, 139, 255], image.get_pixel(1, 0).0);
+    assert_eq!([136, 136, 136, 255], image.get_pixel(2, 0).0);
+    assert_eq!([133, 133, 133, 255], image.get_pixel(3, 0).0);
+    assert_eq!([0, 255, 0, 255], image.get_pixel(3, 3).0);
+
+    // Planar mode: a gradient from red in the top left corner to black.
+    let planar = [0x7E, 0x00, 0x04, 0x02, 0x00, 0x00, 0x00, 0x00];
+    let image = decode(GL_COMPRESSED_RGB8_ETC2, 4, 4, &planar);
+    assert_eq!([255, 0, 0, 255], image.get_pixel(0, 0).0);
+    assert_eq!([191, 0, 0, 255], image.get_pixel(1, 0).0);
+    assert_eq!([191, 0, 0, 255], image.get_pixel(0, 1).0);
+    assert_eq!([0, 0, 0, 255], image.get_pixel(3, 3).0);
+}
+
+#[test]
+fn test_etc2_alpha() {
+    // Not opaque: pixel index 2 is transparent.
+    let punchthrough = [0x80, 0x00, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00];
+    let image = decode(
+        GL_COMPRESSED_RGB8_PUNCHTHROUGH_ALPHA1_ETC2,
+        4,
+        4,
+        &punchthrough,
+    );
+    assert_eq!([0, 0, 0, 0], image.get_pixel(0, 0).0);
+    assert_eq!([132, 0, 0, 255], image.get_pixel(1, 0).0);
+
+    // Alpha of 128 with a multiplier of 1, modified by -3 or +2.
+    let alpha = [0x80, 0x10, 0x00, 0x08, 0x00, 0x00, 0x00, 0x00];
+    let image = decode(
+        GL_COMPRESSED_RGBA8_ETC2_EAC,
+        4,
+        4,
+        &[alpha, SPLIT_BLOCK].concat(),
+    );
+    assert_eq!([247, 0, 0, 125], image.get_pixel(0, 0).0);
+    assert_eq!([255, 2, 2, 130], image.get_pixel(1, 0).0);
+    assert_eq!([2, 255, 2, 125], image.get_pixel(3, 3).0);
+}
+
+#[test]
+fn test_eac() {
+    let red = [0x80, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00];
+    let image = decode(GL_COMPRESSED_R11_EAC, 4, 4, &red);
+    assert!(image.pixels().all(|p| p.0 == [127, 0, 0, 255]));
+}
+
+#[test]
+fn test_too_large() {
+    // Missing blocks are reported before the image is allocated.
+    let data = ktx(0, 0, GL_ETC1_RGB8, u32::MAX, u32::MAX, &SPLIT_BLOCK);
+    match decode_ktx(&data) {
+        Err(Error::UnexpectedEof { offset: 8, .. }) => (),
+        _ => panic!("expected `Error::UnexpectedEof`"),
+    }
+}
@@ -0,0 +1,117 @@
+mod common;
+
+use common::{ascii, ktx, ktx_block, tag, tex_sc, zstd, RED};
+use sc_extract::{decode_ktx, decode_tex_blocks, decode_tex_blocks_in, Error};
+use std::fs;
+
+const GL_UNSIGNED_BYTE: u32 = 0x1401;
+const GL_UNSIGNED_SHORT_4_4_4_4: u32 = 0x8033;
+const GL_UNSIGNED_SHORT_5_6_5: u32 = 0x8363;
+const GL_RGB: u32 = 0x1907;
+const GL_RGBA: u32 = 0x1908;
+
+/// A 2x2 red `RGBA8` image.
+fn red_ktx() -> Vec<u8> {
+    ktx(GL_UNSIGNED_BYTE, GL_RGBA, 0x8058, 2, 2, &RED.repeat(4))
+}
+
+#[test]
+fn test_embedded() {
+    let data = tex_sc(&ktx_block(0, 2, 2, &red_ktx()));
+    let textures = decode_tex_blocks(&data).unwrap();
+    assert_eq!(1, textures.len());
+
+    let texture = textures[0].as_ref().unwrap();
+    assert_eq!(45, texture.file_type);
+    assert_eq!((2, 2), (texture.width, texture.height));
+    assert!(texture.image.pixels().all(|p| p.0 == RED));
+}
+
+#[test]
+fn test_external() {
+    let dir = std::env::temp_dir().join(format!("sc_extract_ktx_{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(dir.join("ui_tex.zktx"), zstd(&red_ktx())).unwrap();
+
+    let mut body = ascii("sc/ui_tex.zktx");
+    body.extend_from_slice(&[0, 2, 0, 2, 0]);
+    let data = tex_sc(&tag(47, &body));
+
+    match &decode_tex_blocks(&data).unwrap()[0] {
+        Err(Error::Unsupported(_)) => (),
+        _ => panic!("external texture decoded without its directory"),
+    }
+
+    let textures = decode_tex_blocks_in(&data, &dir).unwrap();
+    for name in ["../ui_tex.zktx", "/tmp/ui_tex.zktx", ""] {
+        let mut body = ascii(name);
+        body.extend_from_slice(&[0, 2, 0, 2, 0]);
+        match &decode_tex_blocks_in(&tex_sc(&tag(47, &body)), &dir).unwrap()[0] {
+            Err(Error::InvalidData(_)) => (),
+            _ => panic!("expected `Error::InvalidData` for `{}`", name),
+        }
+    }
+    fs::remove_dir_all(&dir).unwrap();
+    let texture = textures[0].as_ref().unwrap();
+    assert_eq!(47, texture.file_type);
+    assert!(texture.image.pixels().all(|p| p.0 == RED));
+}
+
+#[test]
+fn test_formats() {
+    // Rows of 3-byte pixels are padded to 4 bytes.
+    let rgb = decode_ktx(&ktx(
+        GL_UNSIGNED_BYTE,
+        GL_RGB,
+        0x8051,
+        1,
+        2,
+        &[1, 2, 3, 0, 4, 5, 6, 0],
+    ))
+    .unwrap();
+    assert_eq!([4, 5, 6, 255], rgb.get_pixel(0, 1).0);
+
+    let rgb565 = decode_ktx(&ktx(
+        GL_UNSIGNED_SHORT_5_6_5,
+        GL_RGB,
+        0x8D62,
+        1,
+        1,
+        &[0x1F, 0xF8, 0, 0],
+    ))
+    .unwrap();
+    assert_eq!([255, 0, 255, 255], rgb565.get_pixel(0, 0).0);
+
+    let rgba4444 = decode_ktx(&ktx(
+        GL_UNSIGNED_SHORT_4_4_4_4,
+        GL_RGBA,
+        0x8056,
+        1,
+        1,
+        &[0x8F, 0x0F, 0, 0],
+    ))
+    .unwrap();
+    assert_eq!([0, 255, 136, 255], rgba4444.get_pixel(0, 0).0);
+
+    match decode_ktx(b"not a ktx image") {
+        Err(Error::InvalidHeader(_)) => (),
+        r => panic!("unexpected result: {:?}", r.map(|i| i.dimensions())),
+    }
+}
+
+#[test]
+fn test_invalid_sizes() {
+    for &(width, height) in &[(0, 2), (2, 0), (u32::MAX, u32::MAX)] {
+        match decode_ktx(&ktx(
+            GL_UNSIGNED_BYTE,
+            GL_RGBA,
+            0x8058,
+            width,
+            height,
+            &[0; 16],
+        )) {
+            Err(Error::InvalidData(_)) => (),
+            r => panic!("unexpected result: {:?}", r.map(|i| i.dimensions())),
+        }
+    }
+}
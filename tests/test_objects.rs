@@ -0,0 +1,158 @@
+mod common;
+
+use common::{ascii, tag};
+use sc_extract::{decode_objects, objects::Matrix, Error};
+
+/// Builds the decompressed data of an object file with one export and `tags`.
+fn objects_payload(tags: &[Vec<u8>]) -> Vec<u8> {
+    common::objects_payload(&[(2, "button")], tags)
+}
+
+fn twips(values: &[i32]) -> Vec<u8> {
+    values
+        .iter()
+        .flat_map(|v| (v * 20).to_le_bytes().to_vec())
+        .collect()
+}
+
+fn shape() -> Vec<u8> {
+    let mut bitmap = vec![0, 3];
+    bitmap.extend(twips(&[0, 0, 10, 0, 10, 10]));
+    for v in &[0u16, 0, 32, 0, 32, 32] {
+        bitmap.extend_from_slice(&v.to_le_bytes());
+    }
+
+    let mut body = vec![1, 0, 1, 0, 3, 0];
+    body.extend(tag(17, &bitmap));
+    body.extend(tag(0, &[]));
+    tag(18, &body)
+}
+
+fn movie_clip() -> Vec<u8> {
+    // Id 2, 24 fps, 2 frames.
+    let mut body = vec![2, 0, 24, 2, 0];
+    // Two elements, the second one without a color transform.
+    body.extend_from_slice(&2u32.to_le_bytes());
+    body.extend_from_slice(&[0, 0, 0, 0, 0, 0]);
+    body.extend_from_slice(&[1, 0, 0, 0, 0xFF, 0xFF]);
+    // Two children with blend modes and names.
+    body.extend_from_slice(&[2, 0, 1, 0, 3, 0]);
+    body.extend_from_slice(&[0, 1]);
+    body.extend(ascii("icon"));
+    body.push(0xFF);
+    // Frames.
+    let mut frame = vec![1, 0];
+    frame.extend(ascii("idle"));
+    body.extend(tag(11, &frame));
+    body.extend(tag(11, &[1, 0, 0xFF]));
+    body.extend(tag(0, &[]));
+    tag(12, &body)
+}
+
+fn text_field() -> Vec<u8> {
+    let mut body = vec![3, 0];
+    body.extend(ascii("Supercell-Magic"));
+    body.extend_from_slice(&0xFFFF_FFFFu32.to_le_bytes());
+    body.extend_from_slice(&[1, 0, 0, 0, 1, 24]);
+    for v in &[-50i16, -10, 50, 10] {
+        body.extend_from_slice(&v.to_le_bytes());
+    }
+    body.push(1);
+    body.extend(ascii("PLAY"));
+    body.push(0);
+    tag(15, &body)
+}
+
+fn matrix() -> Vec<u8> {
+    let mut body = Vec::new();
+    for v in &[1024i32, 0, 0, 2048] {
+        body.extend_from_slice(&v.to_le_bytes());
+    }
+    body.extend(twips(&[5, -5]));
+    tag(8, &body)
+}
+
+#[test]
+fn test_decode() {
+    let payload = objects_payload(&[
+        tag(1, &[0, 32, 0, 32, 0]),
+        shape(),
+        movie_clip(),
+        text_field(),
+        matrix(),
+        tag(9, &[10, 20, 30, 128, 255, 255, 255]),
+    ]);
+    let objects = decode_objects(&common::tex_sc(&payload)).unwrap();
+
+    assert_eq!(Some("button"), objects.export_name(2));
+    assert_eq!(1, objects.textures.len());
+    assert_eq!(
+        (32, 32),
+        (objects.textures[0].width, objects.textures[0].height)
+    );
+
+    let shape = objects.shape(1).unwrap();
+    assert_eq!(1, shape.bitmaps.len());
+    assert_eq!(
+        vec![(0.0, 0.0), (10.0, 0.0), (10.0, 10.0)],
+        shape.bitmaps[0].xy
+    );
+    assert_eq!(
+        vec![(0.0, 0.0), (32.0, 0.0), (32.0, 32.0)],
+        shape.bitmaps[0].uv
+    );
+
+    let clip = objects.movie_clip(2).unwrap();
+    assert_eq!(24, clip.fps);
+    assert_eq!(2, clip.children.len());
+    assert_eq!(1, clip.children[1].blend_mode);
+    assert_eq!(Some("icon"), clip.children[0].name.as_deref());
+    assert_eq!(None, clip.children[1].name);
+    assert_eq!(2, clip.frames.len());
+    assert_eq!(Some("idle"), clip.frames[0].label.as_deref());
+    assert_eq!(Some(0), clip.frames[0].elements[0].color_transform);
+    assert_eq!(1, clip.frames[1].elements[0].child_index);
+    assert_eq!(None, clip.frames[1].elements[0].color_transform);
+
+    let text = objects.text_field(3).unwrap();
+    assert_eq!(Some("Supercell-Magic"), text.font_name.as_deref());
+    assert_eq!(Some("PLAY"), text.text.as_deref());
+    assert_eq!((-50, -10, 50, 10), text.bounds);
+    assert!(text.bold && text.outline && !text.use_device_font);
+
+    let bank = &objects.matrix_banks[0];
+    assert_eq!(
+        Matrix {
+            a: 1.0,
+            b: 0.0,
+            c: 0.0,
+            d: 2.0,
+            tx: 5.0,
+            ty: -5.0
+        },
+        bank.matrices[0]
+    );
+    assert_eq!(128, bank.color_transforms[0].a_mul);
+}
+
+#[test]
+fn test_matrix_banks() {
+    let payload = objects_payload(&[matrix(), tag(42, &[1, 0, 0, 0]), matrix(), matrix()]);
+    let objects = decode_objects(&common::tex_sc(&payload)).unwrap();
+
+    assert_eq!(2, objects.matrix_banks.len());
+    assert_eq!(1, objects.matrix_banks[0].matrices.len());
+    assert_eq!(2, objects.matrix_banks[1].matrices.len());
+}
+
+#[test]
+fn test_truncated() {
+    let mut clip = movie_clip();
+    clip.truncate(20);
+    let payload = objects_payload(&[clip]);
+
+    match decode_objects(&common::tex_sc(&payload)) {
+        Err(Error::UnexpectedEof { .. }) => (),
+        r => panic!("expected unexpected end of data, got {:?}", r.map(|_| ())),
+    }
+}
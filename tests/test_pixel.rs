@@ -0,0 +1,148 @@
+mod common;
+
+use common::{tex_sc, texture_block};
+use sc_extract::{decode_tex_blocks, Error, PixelFormat};
+
+#[test]
+fn test_sub_types() {
+    let formats = [
+        (0, PixelFormat::Rgba8888),
+        (1, PixelFormat::Rgba8888),
+        (2, PixelFormat::Rgba4444),
+        (3, PixelFormat::Rgba5551),
+        (4, PixelFormat::Rgb565),
+        (6, PixelFormat::La88),
+        (9, PixelFormat::Rgba4444),
+        (10, PixelFormat::L8),
+    ];
+    for (sub_type, format) in &formats {
+        assert_eq!(
+            Some(*format),
+            PixelFormat::from_sub_type(*sub_type, format.size())
+        );
+    }
+
+    assert_eq!(Some(PixelFormat::La44), PixelFormat::from_sub_type(5, 1));
+    assert_eq!(Some(PixelFormat::Rgb555), PixelFormat::from_sub_type(5, 2));
+    assert_eq!(None, PixelFormat::from_sub_type(7, 4));
+}
+
+#[test]
+fn test_rgba8888() {
+    assert_eq!(
+        [1, 2, 3, 4],
+        PixelFormat::Rgba8888.decode(&[1, 2, 3, 4]).unwrap()
+    );
+}
+
+#[test]
+fn test_rgba4444() {
+    assert_eq!(
+        [255, 0, 255, 136],
+        PixelFormat::Rgba4444
+            .decode(&0xF0F8u16.to_le_bytes())
+            .unwrap()
+    );
+}
+
+#[test]
+fn test_rgba5551() {
+    assert_eq!(
+        [255; 4],
+        PixelFormat::Rgba5551.decode(&[0xFF, 0xFF]).unwrap()
+    );
+    assert_eq!(
+        [8, 8, 8, 0],
+        PixelFormat::Rgba5551
+            .decode(&0x0842u16.to_le_bytes())
+            .unwrap()
+    );
+}
+
+#[test]
+fn test_rgb565() {
+    assert_eq!([255; 4], PixelFormat::Rgb565.decode(&[0xFF, 0xFF]).unwrap());
+    assert_eq!(
+        [255, 0, 0, 255],
+        PixelFormat::Rgb565
+            .decode(&0xF800u16.to_le_bytes())
+            .unwrap()
+    );
+    assert_eq!(
+        [0, 255, 0, 255],
+        PixelFormat::Rgb565
+            .decode(&0x07E0u16.to_le_bytes())
+            .unwrap()
+    );
+}
+
+#[test]
+fn test_short_pixels() {
+    assert_eq!(None, PixelFormat::Rgba8888.decode(&[1, 2, 3]));
+    assert_eq!(None, PixelFormat::Rgb565.decode(&[0xFF]));
+    assert_eq!(None, PixelFormat::L8.decode(&[]));
+    // Extra bytes are ignored.
+    assert_eq!(Some([1, 1, 1, 255]), PixelFormat::L8.decode(&[1, 2]));
+}
+
+#[test]
+fn test_la44() {
+    assert_eq!(
+        [255, 255, 255, 119],
+        PixelFormat::La44.decode(&[0xF7]).unwrap()
+    );
+}
+
+#[test]
+fn test_rgb555() {
+    assert_eq!(
+        [255, 0, 0, 255],
+        PixelFormat::Rgb555
+            .decode(&0x7C00u16.to_le_bytes())
+            .unwrap()
+    );
+    assert_eq!(
+        [0, 0, 255, 255],
+        PixelFormat::Rgb555
+            .decode(&0x001Fu16.to_le_bytes())
+            .unwrap()
+    );
+}
+
+#[test]
+fn test_la88() {
+    assert_eq!(
+        [128, 128, 128, 64],
+        PixelFormat::La88.decode(&[0x40, 0x80]).unwrap()
+    );
+}
+
+#[test]
+fn test_l8() {
+    assert_eq!(
+        [127, 127, 127, 255],
+        PixelFormat::L8.decode(&[0x7F]).unwrap()
+    );
+}
+
+#[test]
+fn test_textures() {
+    // Sub-type 5 is told apart by the size of the pixel data.
+    let mut payload = texture_block(1, 5, 2, 1, &[0xF7, 0xF7]);
+    payload.extend(texture_block(1, 5, 2, 1, &[0x00, 0x7C, 0x00, 0x7C]));
+    payload.extend(texture_block(1, 10, 1, 1, &[0x7F]));
+    payload.extend(texture_block(1, 7, 1, 1, &[0]));
+    let blocks = decode_tex_blocks(&tex_sc(&payload)).unwrap();
+
+    let pixel = |i: usize| blocks[i].as_ref().unwrap().image.get_pixel(1, 0).0;
+    assert_eq!([255, 255, 255, 119], pixel(0));
+    assert_eq!([255, 0, 0, 255], pixel(1));
+    assert_eq!(
+        [127, 127, 127, 255],
+        blocks[2].as_ref().unwrap().image.get_pixel(0, 0).0
+    );
+    match &blocks[3] {
+        Err(Error::UnknownPixel { pixel_type: 7, .. }) => (),
+        _ => panic!("expected `Error::UnknownPixel`"),
+    }
+}
@@ -0,0 +1,130 @@
+mod common;
+
+use common::ktx;
+use sc_extract::{decode_image, decode_pvr, Error};
+
+const GL_COMPRESSED_RGBA_PVRTC_4BPPV1_IMG: u32 = 0x8C02;
+
+/// Opaque red, as the first color of a block.
+const RED: u32 = 0xFC00;
+/// Opaque blue, as the second color of a block.
+const BLUE: u32 = 0x801F << 16;
+
+/// A `PVRTC` block with its modulation bits and color data.
+fn block(modulation: u32, colors: u32) -> Vec<u8> {
+    [modulation.to_le_bytes(), colors.to_le_bytes()].concat()
+}
+
+/// A `PVR` (version 3) image.
+fn pvr(pixel_format: u64, width: u32, height: u32, data: &[u8]) -> Vec<u8> {
+    let mut pvr = b"PVR\x03".to_vec();
+    pvr.extend_from_slice(&0u32.to_le_bytes());
+    pvr.extend_from_slice(&pixel_format.to_le_bytes());
+    for value in &[0, 0, height, width, 1, 1, 1, 1, 0] {
+        pvr.extend_from_slice(&u32::to_le_bytes(*value));
+    }
+    pvr.extend_from_slice(data);
+    pvr
+}
+
+/// A legacy `PVR` (version 2) image with an alpha channel.
+fn legacy_pvr(pixel_type: u32, width: u32, height: u32, data: &[u8]) -> Vec<u8> {
+    let mut pvr = Vec::new();
+    let header = [
+        52,
+        height,
+        width,
+        0,
+        pixel_type,
+        data.len() as u32,
+        4,
+        0,
+        0,
+        0,
+        0xFF,
+    ];
+    for value in &header {
+        pvr.extend_from_slice(&u32::to_le_bytes(*value));
+    }
+    pvr.extend_from_slice(b"PVR!");
+    pvr.extend_from_slice(&1u32.to_le_bytes());
+    pvr.extend_from_slice(data);
+    pvr
+}
+
+#[test]
+fn test_4bpp() {
+    // The first pixel of each block is blue and the second one is 3/8 blue.
+    let blocks = block(0b0111, RED | BLUE).repeat(4);
+    let image = decode_pvr(&pvr(3, 8, 8, &blocks)).unwrap();
+    assert_eq!((8, 8), image.dimensions());
+    assert_eq!([0, 0, 255, 255], image.get_pixel(4, 4).0);
+    assert_eq!([159, 0, 95, 255], image.get_pixel(5, 4).0);
+    assert_eq!([255, 0, 0, 255], image.get_pixel(7, 7).0);
+
+    // In punch-through mode, the modulation value `2` makes pixels transparent.
+    let blocks = block(0b10, RED | BLUE | 1).repeat(4);
+    let image = decode_image(&pvr(3, 8, 8, &blocks)).unwrap();
+    assert_eq!([127, 0, 127, 0], image.get_pixel(0, 0).0);
+    assert_eq!([255, 0, 0, 255], image.get_pixel(1, 0).0);
+
+    // Formats without alpha are opaque.
+    let image = decode_pvr(&pvr(2, 8, 8, &blocks)).unwrap();
+    assert_eq!([127, 0, 127, 255], image.get_pixel(0, 0).0);
+}
+
+#[test]
+fn test_interpolation() {
+    // Blocks are stored in Morton order, and their colors are interpolated between
+    // their centers. Block `i` has a red level of `4 * i` out of 31.
+    let blocks: Vec<u8> = (0..8)
+        .flat_map(|i| block(0, 0x8000 | (4 * i) << 10))
+        .collect();
+    let ktx = ktx(0, 0, GL_COMPRESSED_RGBA_PVRTC_4BPPV1_IMG, 16, 8, &blocks);
+    let image = decode_image(&ktx).unwrap();
+    assert_eq!(0, image.get_pixel(2, 2).0[0]);
+    assert_eq!(66, image.get_pixel(6, 2).0[0]);
+    assert_eq!(165, image.get_pixel(10, 6).0[0]);
+    // Halfway between the first block and the block on its right, stored third.
+    assert_eq!(33, image.get_pixel(4, 2).0[0]);
+}
+
+#[test]
+fn test_2bpp() {
+    let blocks = block(0xFFFF_0000, RED | BLUE).repeat(4);
+    let image = decode_pvr(&legacy_pvr(0x18, 16, 8, &blocks)).unwrap();
+    assert_eq!((16, 8), image.dimensions());
+    assert_eq!([255, 0, 0, 255], image.get_pixel(7, 1).0);
+    assert_eq!([0, 0, 255, 255], image.get_pixel(0, 2).0);
+
+    // Pixels which aren't stored are averaged from their neighbours.
+    let blocks = block(0xAAAA_AAAA, RED | BLUE | 1).repeat(4);
+    let image = decode_pvr(&legacy_pvr(0x18, 16, 8, &blocks)).unwrap();
+    assert_eq!([95, 0, 159, 255], image.get_pixel(1, 1).0);
+    assert_eq!([95, 0, 159, 255], image.get_pixel(2, 1).0);
+}
+
+#[test]
+fn test_errors() {
+    let blocks = block(0, RED).repeat(6);
+    match decode_pvr(&pvr(3, 12, 8, &blocks)) {
+        Err(Error::Unsupported(_)) => (),
+        r => panic!("unexpected result: {:?}", r.map(|i| i.dimensions())),
+    }
+    match decode_pvr(&pvr(3, 8, 8, &blocks[..16])) {
+        Err(Error::UnexpectedEof { needed: 16, .. }) => (),
+        r => panic!("unexpected result: {:?}", r.map(|i| i.dimensions())),
+    }
+    match decode_pvr(&pvr(16, 8, 8, &blocks)) {
+        Err(Error::Unsupported(_)) => (),
+        r => panic!("unexpected result: {:?}", r.map(|i| i.dimensions())),
+    }
+    match decode_pvr(&pvr(3, 65536, 65536, &blocks)) {
+        Err(Error::InvalidData(_)) => (),
+        r => panic!("unexpected result: {:?}", r.map(|i| i.dimensions())),
+    }
+    match decode_pvr(&pvr(3, 32768, 32768, &blocks)) {
+        Err(Error::UnexpectedEof { offset: 48, .. }) => (),
+        r => panic!("unexpected result: {:?}", r.map(|i| i.dimensions())),
+    }
+}
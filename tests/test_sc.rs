@@ -1,13 +1,17 @@
+mod common;
+
 use rayon::prelude::*;
-use sc_extract::process_sc;
+use sc_extract::{
+    decode_tex, decode_tex_blocks, process_sc, process_sc_with_mipmaps, verify_sc, Error, ScHeader,
+};
 use std::{fs, path::Path};
 
 #[test]
 fn test_single() {
     let path = Path::new("./tests/data/sc/background_basic_tex.sc");
-    let data = fs::read(&path).unwrap();
+    let data = fs::read(path).unwrap();
     let out_dir = Path::new("./tests/out/sc");
-    assert_eq!(true, process_sc(data.as_slice(), &path, &out_dir, true).is_ok());
+    assert!(process_sc(data.as_slice(), path, out_dir, true).is_ok());
 }
 
 #[test]
@@ -16,7 +20,7 @@ fn test_all_parallel() {
     let out_dir = Path::new("./tests/out/sc");
     let dir_entries = match fs::read_dir(dir) {
         Ok(e) => e,
-        Err(_) => std::process::exit(1)
+        Err(_) => std::process::exit(1),
     };
 
     let mut entries = Vec::new();
@@ -27,7 +31,7 @@ fn test_all_parallel() {
     entries.into_par_iter().for_each(|entry| {
         let path = entry.unwrap().path();
         let data = fs::read(&path).unwrap();
-        assert_eq!(true, process_sc(data.as_slice(), &path, &out_dir, true).is_ok());
+        assert!(process_sc(data.as_slice(), &path, out_dir, true).is_ok());
     });
 }
 
@@ -37,7 +41,7 @@ fn test_all_blocking() {
     let out_dir = Path::new("./tests/out/sc");
     let dir_entries = match fs::read_dir(dir) {
         Ok(e) => e,
-        Err(_) => std::process::exit(1)
+        Err(_) => std::process::exit(1),
     };
 
     let mut entries = Vec::new();
@@ -48,6 +52,235 @@ fn test_all_blocking() {
     for entry in entries {
         let path = entry.unwrap().path();
         let data = fs::read(&path).unwrap();
-        assert_eq!(true, process_sc(data.as_slice(), &path, &out_dir, false).is_ok());
+        assert!(process_sc(data.as_slice(), &path, out_dir, false).is_ok());
+    }
+}
+
+#[test]
+fn test_decode() {
+    let path = Path::new("./tests/data/sc/background_basic_tex.sc");
+    let data = fs::read(path).unwrap();
+    let textures = decode_tex(data.as_slice()).unwrap();
+
+    assert_eq!(2, textures.len());
+    for texture in textures {
+        assert_eq!(texture.width as u32, texture.image.width());
+        assert_eq!(texture.height as u32, texture.image.height());
+    }
+}
+
+#[test]
+fn test_too_small() {
+    match decode_tex(b"SC") {
+        Err(Error::UnexpectedEof { offset, .. }) => assert_eq!(2, offset),
+        _ => panic!("expected `Error::UnexpectedEof`"),
+    }
+}
+
+#[test]
+fn test_truncated_texture() {
+    let mut payload = common::texture_block(1, 0, 2, 2, &[255; 16]);
+    payload.extend(common::texture_block(1, 0, 4, 4, &[255; 16]));
+    let data = common::tex_sc(&payload);
+
+    let blocks = decode_tex_blocks(&data).unwrap();
+    assert_eq!(2, blocks.len());
+    assert!(blocks[0].is_ok());
+    match &blocks[1] {
+        // Reported at the start of the pixels, before anything is decoded.
+        Err(Error::UnexpectedEof { offset, needed }) => {
+            assert_eq!(payload.len() - 16, *offset);
+            assert_eq!(48, *needed);
+        }
+        _ => panic!("expected `Error::UnexpectedEof`"),
+    }
+
+    assert_eq!(1, decode_tex(&data).unwrap().len());
+}
+
+#[test]
+fn test_zstd() {
+    let payload = common::texture_block(1, 0, 2, 1, &[1, 2, 3, 4, 5, 6, 7, 8]);
+    let mut data = common::tex_sc(&[]);
+    data.truncate(26);
+    data.extend(common::zstd(&payload));
+
+    let textures = decode_tex(&data).unwrap();
+    assert_eq!(1, textures.len());
+    assert_eq!(
+        &[1, 2, 3, 4, 5, 6, 7, 8],
+        textures[0].image.as_raw().as_slice()
+    );
+}
+
+#[test]
+fn test_header() {
+    let data = fs::read("./tests/data/sc/background_basic_tex.sc").unwrap();
+    let header = ScHeader::parse(&data).unwrap();
+
+    assert_eq!(1, header.version);
+    assert_eq!(16, header.hash.len());
+    assert_eq!(26, header.size());
+    assert!(header.metadata.is_none());
+    assert_eq!(&data[26..], header.payload(&data));
+}
+
+#[test]
+fn test_header_v4() {
+    let payload = common::compress(&common::texture_block(1, 0, 1, 1, &[1, 2, 3, 4]));
+
+    let mut data = b"SC".to_vec();
+    data.extend_from_slice(&[0, 0, 0, 4, 0, 0, 0, 1, 0, 0, 0, 4]);
+    data.extend_from_slice(&[9, 8, 7, 6]);
+    data.extend(&payload);
+    data.extend_from_slice(b"STARTsome metadata");
+
+    let header = ScHeader::parse(&data).unwrap();
+    assert_eq!(1, header.version);
+    assert_eq!(vec![9, 8, 7, 6], header.hash);
+    assert_eq!(18, header.size());
+    assert_eq!(Some(b"some metadata".to_vec()), header.metadata);
+    assert_eq!(payload.as_slice(), header.payload(&data));
+
+    let textures = decode_tex(&data).unwrap();
+    assert_eq!(&[1, 2, 3, 4], textures[0].image.as_raw().as_slice());
+}
+
+#[test]
+fn test_invalid_header() {
+    match decode_tex(&[0; 64]) {
+        Err(Error::InvalidHeader(_)) => (),
+        _ => panic!("expected `Error::InvalidHeader`"),
+    }
+}
+
+#[test]
+fn test_verify() {
+    let mut data = fs::read("./tests/data/sc/background_scity_tex.sc").unwrap();
+    assert!(verify_sc(&data).is_ok());
+
+    data[10] ^= 0xFF;
+    match verify_sc(&data) {
+        Err(Error::HashMismatch { expected, actual }) => {
+            assert_eq!(&data[10..26], expected.as_slice());
+            assert_ne!(expected, actual);
+        }
+        _ => panic!("expected `Error::HashMismatch`"),
+    }
+}
+
+#[test]
+fn test_signed() {
+    let inner = fs::read("./tests/data/sc/background_scity_tex.sc").unwrap();
+    let mut data = b"Sig:".to_vec();
+    data.extend_from_slice(&[7; 64]);
+    data.extend(&inner);
+
+    let header = ScHeader::parse(&data).unwrap();
+    assert_eq!(Some(vec![7; 64]), header.signature);
+    assert_eq!(68 + 26, header.size());
+    assert_eq!(&inner[26..], header.payload(&data));
+
+    assert!(verify_sc(&data).is_ok());
+    assert_eq!(2, decode_tex(&data).unwrap().len());
+}
+
+#[test]
+fn test_mipmaps() {
+    // A 4x2 texture with levels of 2x1 and 1x1 pixels, then a second texture.
+    let pixels: Vec<u8> = (0..8 + 2 + 1).flat_map(|i| [i; 4]).collect();
+    let mut payload = common::texture_block(1, 0, 4, 2, &pixels);
+    payload.extend(common::texture_block(1, 0, 1, 1, &[9; 4]));
+    let data = common::tex_sc(&payload);
+
+    let textures = decode_tex(&data).unwrap();
+    assert_eq!(2, textures.len());
+    let mipmaps = &textures[0].mipmaps;
+    assert_eq!(2, mipmaps.len());
+    assert_eq!((2, 1), mipmaps[0].dimensions());
+    assert_eq!([9; 4], mipmaps[0].get_pixel(1, 0).0);
+    assert_eq!([10; 4], mipmaps[1].get_pixel(0, 0).0);
+    assert_eq!([9; 4], textures[1].image.get_pixel(0, 0).0);
+    assert!(textures[1].mipmaps.is_empty());
+
+    let out_dir = std::env::temp_dir().join(format!("sc_extract_mip_{}", std::process::id()));
+    fs::create_dir_all(&out_dir).unwrap();
+    let path = Path::new("ui_tex.sc");
+    process_sc_with_mipmaps(&data, path, &out_dir, true).unwrap();
+    let level = image::open(out_dir.join("ui_tex_mip2.png")).unwrap();
+    assert!(out_dir.join("ui_tex_mip1.png").exists());
+    assert!(out_dir.join("ui_tex_.png").exists());
+    assert!(!out_dir.join("ui_tex__mip1.png").exists());
+    fs::remove_dir_all(&out_dir).unwrap();
+    assert_eq!((1, 1), level.to_rgba8().dimensions());
+}
+
+#[test]
+fn test_texture_tags() {
+    let mut payload = Vec::new();
+    for file_type in [1, 16, 19, 24, 34] {
+        payload.extend(common::texture_block(file_type, 0, 1, 1, &[file_type; 4]));
+    }
+    let textures = decode_tex(&common::tex_sc(&payload)).unwrap();
+    assert_eq!(5, textures.len());
+    assert_eq!([34; 4], textures[4].image.get_pixel(0, 0).0);
+
+    // Pixels of tiled textures are stored by tiles of 32x32 pixels.
+    let pixels: Vec<u8> = (0..66).flat_map(|i| [i; 4]).collect();
+    for file_type in [27, 28, 29] {
+        let block = common::texture_block(file_type, 0, 33, 2, &pixels);
+        let textures = decode_tex(&common::tex_sc(&block)).unwrap();
+        let image = &textures[0].image;
+        assert_eq!([32; 4], image.get_pixel(0, 1).0);
+        assert_eq!([64; 4], image.get_pixel(32, 0).0);
+        assert_eq!([65; 4], image.get_pixel(32, 1).0);
     }
 }
+
+#[test]
+fn test_trailing_bytes() {
+    // Bytes which don't make a mipmap level are skipped with their block.
+    let mut payload = common::texture_block(1, 0, 2, 1, &[1; 11]);
+    payload.extend(common::texture_block(1, 0, 1, 1, &[2; 4]));
+    let textures = decode_tex(&common::tex_sc(&payload)).unwrap();
+
+    assert_eq!(2, textures.len());
+    assert!(textures[0].mipmaps.is_empty());
+    assert_eq!([2; 4], textures[1].image.get_pixel(0, 0).0);
+}
+
+#[test]
+fn test_damaged_blocks() {
+    // A texture with an unknown pixel type and a texture with less pixels than its
+    // size, followed by valid textures.
+    let mut payload = common::texture_block(1, 7, 2, 2, &[0xFF; 8]);
+    payload.extend(common::texture_block(1, 0, 4, 4, &[0xFF; 16]));
+    payload.extend(common::texture_block(1, 0, 1, 1, &[1, 2, 3, 4]));
+    payload.extend(common::texture_block(24, 6, 1, 1, &[5, 6]));
+    payload.extend(common::texture_block(0, 0, 0, 0, &[]));
+    let data = common::tex_sc(&payload);
+
+    let blocks = decode_tex_blocks(&data).unwrap();
+    assert_eq!(4, blocks.len());
+    match &blocks[0] {
+        Err(Error::UnknownPixel { pixel_type: 7, .. }) => (),
+        _ => panic!("expected `Error::UnknownPixel`"),
+    }
+    match &blocks[1] {
+        Err(Error::UnexpectedEof { .. }) => (),
+        _ => panic!("expected `Error::UnexpectedEof`"),
+    }
+
+    let textures = decode_tex(&data).unwrap();
+    assert_eq!(2, textures.len());
+    assert_eq!(&[1, 2, 3, 4], textures[0].image.as_raw().as_slice());
+    assert_eq!([6, 6, 6, 5], textures[1].image.get_pixel(0, 0).0);
+
+    // A block larger than the remaining data ends decoding.
+    let mut payload = common::texture_block(1, 0, 1, 1, &[1, 2, 3, 4]);
+    payload.extend_from_slice(&[1, 64, 0, 0, 0, 0, 1, 0]);
+    let blocks = decode_tex_blocks(&common::tex_sc(&payload)).unwrap();
+    assert_eq!(2, blocks.len());
+    assert!(blocks[0].is_ok());
+    assert!(blocks[1].is_err());
+}
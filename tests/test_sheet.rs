@@ -0,0 +1,113 @@
+mod common;
+
+use common::{animated_sc, red_tex_sc, GREEN, RED};
+use sc_extract::{
+    decode_animations, decode_objects, decode_tex, pack_sheet, process_sheets, AnimationFormat,
+    RenderOptions,
+};
+use std::{fs, path::Path};
+
+#[test]
+fn test_pack() {
+    let animations = decode_animations(
+        &animated_sc(),
+        &red_tex_sc(),
+        None,
+        &RenderOptions::default(),
+    )
+    .unwrap();
+    let sheet = pack_sheet(&animations[0], "walk_sheet.png", 1.0);
+    let data = &sheet.data;
+
+    assert_eq!(2, data.frames.len());
+    assert_eq!(
+        vec!["walk_0000.png", "walk_0001.png"],
+        data.animations["walk"]
+    );
+    assert_eq!("walk_sheet.png", data.meta.image);
+    assert_eq!(
+        (sheet.image.width(), sheet.image.height()),
+        (data.meta.size.w, data.meta.size.h)
+    );
+
+    // Both frames are trimmed to the 2x2 square.
+    let first = &data.frames["walk_0000.png"];
+    assert!(first.trimmed);
+    assert_eq!((2, 2), (first.frame.w, first.frame.h));
+    assert_eq!(
+        (0, 0),
+        (first.sprite_source_size.x, first.sprite_source_size.y)
+    );
+    assert_eq!((4, 2), (first.source_size.w, first.source_size.h));
+    assert_eq!(100, first.duration);
+    assert_eq!((0.0, 0.0), (first.pivot.x, first.pivot.y));
+    assert_eq!(RED, sheet.image.get_pixel(first.frame.x, first.frame.y).0);
+
+    let second = &data.frames["walk_0001.png"];
+    assert_eq!(
+        (2, 0),
+        (second.sprite_source_size.x, second.sprite_source_size.y)
+    );
+    assert_eq!(
+        GREEN,
+        sheet.image.get_pixel(second.frame.x, second.frame.y).0
+    );
+    assert_ne!(first.frame, second.frame);
+}
+
+#[test]
+fn test_json() {
+    let animations = decode_animations(
+        &animated_sc(),
+        &red_tex_sc(),
+        None,
+        &RenderOptions::default(),
+    )
+    .unwrap();
+    let json = pack_sheet(&animations[0], "walk_sheet.png", 1.0)
+        .to_json()
+        .unwrap();
+
+    for key in &[
+        "\"frames\"",
+        "\"spriteSourceSize\"",
+        "\"sourceSize\"",
+        "\"pivot\"",
+        "\"duration\"",
+        "\"animations\"",
+        "\"meta\"",
+    ] {
+        assert!(json.contains(key), "missing {} in {}", key, json);
+    }
+}
+
+#[test]
+fn test_process_with_animations() {
+    let objects = decode_objects(&animated_sc()).unwrap();
+    let textures: Vec<_> = decode_tex(&red_tex_sc())
+        .unwrap()
+        .into_iter()
+        .map(Some)
+        .collect();
+    let out_dir = std::env::temp_dir().join(format!("sc_extract_sheet_{}", std::process::id()));
+    let options = RenderOptions::default();
+
+    let format = Some(AnimationFormat::Gif);
+    process_sheets(
+        &objects,
+        &textures,
+        Path::new("ui.sc"),
+        &out_dir,
+        format,
+        &options,
+        true,
+    )
+    .unwrap();
+    let names: Vec<_> = ["walk_sheet.png", "walk_sheet.json", "walk_anim.gif"]
+        .iter()
+        .filter(|name| out_dir.join("ui").join(name).exists())
+        .collect();
+    fs::remove_dir_all(&out_dir).unwrap();
+
+    assert_eq!(3, names.len());
+}
@@ -0,0 +1,183 @@
+mod common;
+
+use common::{shape, tag};
+use sc_extract::{decode_objects, decode_sprites, decode_tex, process_sprites, Error};
+use std::{fs, path::Path};
+
+/// Pixel of the test texture at `(x, y)`.
+fn texel(x: u32, y: u32) -> [u8; 4] {
+    [x as u8 * 60, y as u8 * 100, 0, 255]
+}
+
+/// Returns the pixels of the test texture, row by row.
+fn texels() -> Vec<u8> {
+    let mut pixels = Vec::new();
+    for y in 0..2 {
+        for x in 0..4 {
+            pixels.extend_from_slice(&texel(x, y));
+        }
+    }
+    pixels
+}
+
+/// Builds a `_tex.sc` file with a single 4x2 texture.
+fn tex_file() -> Vec<u8> {
+    common::tex_sc(&common::texture_block(1, 0, 4, 2, &texels()))
+}
+
+#[test]
+fn test_decode() {
+    let square = [(0, 0), (4, 0), (4, 2), (0, 2)];
+    let objects = common::objects_payload(
+        &[(1, "icon")],
+        &[
+            tag(1, &[0, 4, 0, 2, 0]),
+            shape(1, &square, &[(0, 0), (4, 0), (4, 2), (0, 2)]),
+            // Stored rotated by 90 degrees in the atlas.
+            shape(
+                2,
+                &[(0, 0), (2, 0), (2, 4), (0, 4)],
+                &[(0, 2), (0, 0), (4, 0), (4, 2)],
+            ),
+            // Defined at half the resolution of the texture.
+            shape(
+                3,
+                &[(-1, -1), (1, -1), (1, 0), (-1, 0)],
+                &[(0, 0), (4, 0), (4, 2), (0, 2)],
+            ),
+        ],
+    );
+    let sprites: Vec<_> = decode_sprites(&common::tex_sc(&objects), &tex_file(), None)
+        .unwrap()
+        .into_iter()
+        .map(Result::unwrap)
+        .collect();
+    assert_eq!(3, sprites.len());
+
+    let plain = &sprites[0];
+    assert_eq!(Some("icon"), plain.name.as_deref());
+    assert_eq!((4, 2), plain.image.dimensions());
+    for (x, y, pixel) in plain.image.enumerate_pixels() {
+        assert_eq!(texel(x, y), pixel.0);
+    }
+
+    let rotated = &sprites[1];
+    assert_eq!(None, rotated.name);
+    assert_eq!((2, 4), rotated.image.dimensions());
+    for (x, y, pixel) in rotated.image.enumerate_pixels() {
+        assert_eq!(texel(y, 1 - x), pixel.0);
+    }
+
+    let scaled = &sprites[2];
+    assert_eq!((4, 2), scaled.image.dimensions());
+    for (x, y, pixel) in scaled.image.enumerate_pixels() {
+        assert_eq!(texel(x, y), pixel.0);
+    }
+}
+
+#[test]
+fn test_missing_texture() {
+    let objects = common::objects_payload(
+        &[],
+        &[shape(
+            1,
+            &[(0, 0), (2, 0), (2, 2)],
+            &[(0, 0), (2, 0), (2, 2)],
+        )],
+    );
+    let sprites = decode_sprites(&common::tex_sc(&objects), &common::tex_sc(&[]), None).unwrap();
+
+    assert_eq!(1, sprites.len());
+    let sprite = sprites[0].as_ref().unwrap();
+    assert!(sprite.image.pixels().all(|p| p.0 == [0; 4]));
+}
+
+#[test]
+fn test_external_texture() {
+    let dir = std::env::temp_dir().join(format!("sc_extract_sprites_{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let ktx = common::ktx(0x1401, 0x1908, 0x8058, 4, 2, &texels());
+    fs::write(dir.join("ui_tex.ktx"), ktx).unwrap();
+
+    let mut body = common::ascii("ui_tex.ktx");
+    body.extend_from_slice(&[0, 4, 0, 2, 0]);
+    let tex = common::tex_sc(&tag(47, &body));
+    let square = [(0, 0), (4, 0), (4, 2), (0, 2)];
+    let objects = common::tex_sc(&common::objects_payload(
+        &[],
+        &[
+            tag(1, &[0, 4, 0, 2, 0]),
+            shape(1, &square, &[(0, 0), (4, 0), (4, 2), (0, 2)]),
+        ],
+    ));
+
+    let sprites = decode_sprites(&objects, &tex, Some(&dir)).unwrap();
+    fs::remove_dir_all(&dir).unwrap();
+    let sprite = sprites[0].as_ref().unwrap();
+    for (x, y, pixel) in sprite.image.enumerate_pixels() {
+        assert_eq!(texel(x, y), pixel.0);
+    }
+
+    let sprites = decode_sprites(&objects, &tex, None).unwrap();
+    let sprite = sprites[0].as_ref().unwrap();
+    assert!(sprite.image.pixels().all(|p| p.0 == [0; 4]));
+}
+
+#[test]
+fn test_too_large() {
+    // Drawn at the resolution of its texture coordinates, 10000 pixels wide.
+    let huge = [(0, 0), (10000, 0), (10000, 1), (0, 1)];
+    let objects = common::objects_payload(
+        &[],
+        &[
+            tag(1, &[0, 4, 0, 2, 0]),
+            shape(1, &huge, &[(0, 0), (10000, 0), (10000, 1), (0, 1)]),
+            shape(
+                2,
+                &[(0, 0), (4, 0), (4, 2), (0, 2)],
+                &[(0, 0), (4, 0), (4, 2), (0, 2)],
+            ),
+        ],
+    );
+    let sprites = decode_sprites(&common::tex_sc(&objects), &tex_file(), None).unwrap();
+
+    assert_eq!(2, sprites.len());
+    match &sprites[0] {
+        Err(Error::Unsupported(_)) => (),
+        _ => panic!("expected `Error::Unsupported`"),
+    }
+    assert_eq!((4, 2), sprites[1].as_ref().unwrap().image.dimensions());
+}
+
+#[test]
+fn test_process() {
+    // Shapes sharing an export name, and a shape with an empty export name.
+    let square = [(0, 0), (4, 0), (4, 2), (0, 2)];
+    let uv = [(0, 0), (4, 0), (4, 2), (0, 2)];
+    let objects = common::objects_payload(
+        &[(1, "icon"), (2, "icon"), (3, "")],
+        &[
+            tag(1, &[0, 4, 0, 2, 0]),
+            shape(1, &square, &uv),
+            shape(2, &square, &uv),
+            shape(3, &square, &uv),
+        ],
+    );
+    let objects = decode_objects(&common::tex_sc(&objects)).unwrap();
+    let textures: Vec<_> = decode_tex(&tex_file())
+        .unwrap()
+        .into_iter()
+        .map(Some)
+        .collect();
+
+    let out_dir = std::env::temp_dir().join(format!("sc_extract_cut_{}", std::process::id()));
+    process_sprites(&objects, &textures, Path::new("ui.sc"), &out_dir, true).unwrap();
+    let mut names: Vec<_> = fs::read_dir(out_dir.join("ui"))
+        .unwrap()
+        .map(|e| e.unwrap().file_name().into_string().unwrap())
+        .collect();
+    fs::remove_dir_all(&out_dir).unwrap();
+
+    names.sort();
+    assert_eq!(vec!["icon_1.png", "icon_2.png", "shape_3.png"], names);
+}
@@ -0,0 +1,79 @@
+mod common;
+
+use common::{animated_sc, shape, tag};
+use sc_extract::{atlas_names, decode_objects, export_svgs, movie_clip_to_svg, process_svgs};
+use std::{fs, path::Path};
+
+#[test]
+fn test_shape() {
+    let objects = decode_objects(&animated_sc()).unwrap();
+    let atlases = atlas_names("animated_tex", objects.textures.len());
+    assert_eq!(vec!["animated_tex.png"], atlases);
+
+    let documents = export_svgs(&objects, &atlases, false);
+    assert_eq!(2, documents.len());
+
+    let shape = &documents[0];
+    assert_eq!(1, shape.id);
+    assert_eq!(None, shape.name);
+    assert!(shape.svg.contains(r#"viewBox="0 0 2 2""#));
+    assert!(shape.svg.contains(r#"<polygon points="0,0 2,0 2,2 0,2"/>"#));
+    assert!(shape.svg.contains(
+        r#"<image xlink:href="animated_tex.png" width="2" height="2" clip-path="url(#shape_1_0)"/>"#
+    ));
+}
+
+#[test]
+fn test_movie_clip() {
+    let objects = decode_objects(&animated_sc()).unwrap();
+    let atlases = atlas_names("animated_tex", objects.textures.len());
+    let clip = &objects.movie_clips[0];
+
+    let documents = export_svgs(&objects, &atlases, false);
+    assert_eq!(Some("walk"), documents[1].name.as_deref());
+    let first = &documents[1].svg;
+    assert!(first.contains(r#"viewBox="0 0 2 2""#));
+    assert!(first.contains(r##"<g id="frame_0"><use xlink:href="#shape_1"/></g>"##));
+    assert!(!first.contains("frame_1"));
+
+    let all = movie_clip_to_svg(&objects, clip, &atlases, true).unwrap();
+    assert!(all.contains(r#"viewBox="0 0 4 2""#));
+    assert!(all.contains(r#"<g id="frame_1" display="none">"#));
+    assert!(all.contains(r#"transform="matrix(1 0 0 1 2 0)" filter="url(#color_0)""#));
+    assert!(all.contains(r#"values="0 0 0 0 0 0 1 0 0 1 0 0 1 0 0 0 0 0 1 0""#));
+    // Shapes are only defined once.
+    assert_eq!(1, all.matches(r#"<g id="shape_1">"#).count());
+}
+
+#[test]
+fn test_process() {
+    // A shape and a movie clip sharing an export name, and an unnamed shape.
+    let square = [(0, 0), (2, 0), (2, 2), (0, 2)];
+    let mut clip = vec![2, 0, 24, 1, 0];
+    clip.extend_from_slice(&1u32.to_le_bytes());
+    clip.extend_from_slice(&[0, 0, 0xFF, 0xFF, 0xFF, 0xFF]);
+    clip.extend_from_slice(&[1, 0, 1, 0, 0xFF]);
+    clip.extend(tag(11, &[1, 0, 0xFF]));
+    clip.extend(tag(0, &[]));
+    let payload = common::objects_payload(
+        &[(1, "button"), (2, "button")],
+        &[
+            tag(1, &[0, 2, 0, 2, 0]),
+            shape(1, &square, &[(0, 0), (2, 0), (2, 2), (0, 2)]),
+            shape(3, &square, &[(0, 0), (2, 0), (2, 2), (0, 2)]),
+            tag(10, &clip),
+        ],
+    );
+    let objects = decode_objects(&common::tex_sc(&payload)).unwrap();
+
+    let out_dir = std::env::temp_dir().join(format!("sc_extract_svg_{}", std::process::id()));
+    process_svgs(&objects, Path::new("ui.sc"), &out_dir, false, true).unwrap();
+    let mut names: Vec<_> = fs::read_dir(out_dir.join("ui"))
+        .unwrap()
+        .map(|e| e.unwrap().file_name().into_string().unwrap())
+        .collect();
+    fs::remove_dir_all(&out_dir).unwrap();
+
+    names.sort();
+    assert_eq!(vec!["button_1.svg", "button_2.svg", "shape_3.svg"], names);
+}
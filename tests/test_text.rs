@@ -0,0 +1,125 @@
+mod common;
+
+use common::{ascii, tag};
+use sc_extract::{
+    decode_objects, process_text_fields, render_text_field, text_styles, Error, PreviewFont,
+    ScObjects,
+};
+use std::{fs, path::Path};
+
+/// Builds a text field tag with white, outlined and centered text.
+fn text_field(id: u16, text: Option<&str>) -> Vec<u8> {
+    let mut body = id.to_le_bytes().to_vec();
+    body.extend(ascii("Supercell-Magic"));
+    body.extend_from_slice(&0xFFFF_FFFFu32.to_le_bytes());
+    // Bold, italic, multiline, unknown, align and font size.
+    body.extend_from_slice(&[0, 0, 1, 0, 2, 20]);
+    for v in &[-60i16, -15, 60, 15] {
+        body.extend_from_slice(&v.to_le_bytes());
+    }
+    body.push(1);
+    match text {
+        Some(text) => body.extend(ascii(text)),
+        None => body.push(0xFF),
+    }
+    // Device font and outline color.
+    body.push(0);
+    body.extend_from_slice(&0xFF00_00FFu32.to_le_bytes());
+    tag(21, &body)
+}
+
+/// Builds objects with two text fields, the first one named by a movie clip.
+fn objects() -> ScObjects {
+    let mut clip = vec![3, 0, 24, 0, 0];
+    clip.extend_from_slice(&0u32.to_le_bytes());
+    clip.extend_from_slice(&[1, 0, 1, 0]);
+    clip.extend(ascii("title"));
+    clip.extend(tag(0, &[]));
+
+    let payload = common::objects_payload(
+        &[],
+        &[
+            text_field(1, Some("PLAY")),
+            text_field(2, None),
+            tag(10, &clip),
+        ],
+    );
+    decode_objects(&common::tex_sc(&payload)).unwrap()
+}
+
+#[test]
+fn test_styles() {
+    let styles = text_styles(&objects());
+    assert_eq!(2, styles.len());
+
+    let style = &styles[0];
+    assert_eq!(vec!["title"], style.names);
+    assert_eq!(Some("Supercell-Magic"), style.font_name.as_deref());
+    assert_eq!(20, style.font_size);
+    assert_eq!("#ffffffff", style.color);
+    assert_eq!(Some("#0000ffff"), style.outline_color.as_deref());
+    assert_eq!("center", style.align);
+    assert!(style.multiline && style.outline && !style.bold);
+    assert_eq!((-60, 15), (style.bounds.left, style.bounds.bottom));
+    assert_eq!(Some("PLAY"), style.text.as_deref());
+
+    assert!(styles[1].names.is_empty());
+    assert_eq!(None, styles[1].text);
+}
+
+#[test]
+fn test_preview() {
+    let objects = objects();
+    let font = PreviewFont::fallback();
+
+    let image = render_text_field(&objects.text_fields[0], &font)
+        .unwrap()
+        .unwrap();
+    assert_eq!((120, 30), image.dimensions());
+    assert!(image.pixels().any(|p| p.0 == [255, 255, 255, 255]));
+    assert!(image.pixels().any(|p| p.0 == [0, 0, 255, 255]));
+    // The text is centered, so the corners are empty.
+    assert_eq!([0; 4], image.get_pixel(0, 0).0);
+    assert_eq!([0; 4], image.get_pixel(119, 29).0);
+
+    assert!(render_text_field(&objects.text_fields[1], &font)
+        .unwrap()
+        .is_none());
+}
+
+#[test]
+fn test_too_large() {
+    let mut objects = objects();
+    objects.text_fields[0].bounds = (i16::MIN, i16::MIN, i16::MAX, i16::MAX);
+    let font = PreviewFont::fallback();
+
+    match render_text_field(&objects.text_fields[0], &font) {
+        Err(Error::Unsupported(_)) => (),
+        _ => panic!("expected `Error::Unsupported`"),
+    }
+
+    // The preview is skipped and the styles are still saved.
+    let out_dir = std::env::temp_dir().join(format!("sc_extract_large_{}", std::process::id()));
+    process_text_fields(&objects, Path::new("ui.sc"), &out_dir, Some(&font), true).unwrap();
+    let preview = out_dir.join("ui/title_1.png").exists();
+    let styles = out_dir.join("ui/text_fields.json").exists();
+    fs::remove_dir_all(&out_dir).unwrap();
+    assert!(!preview && styles);
+}
+
+#[test]
+fn test_invalid_font() {
+    assert!(PreviewFont::from_data(vec![0; 16]).is_err());
+}
+
+#[test]
+fn test_process() {
+    let out_dir = std::env::temp_dir().join(format!("sc_extract_text_{}", std::process::id()));
+    let font = PreviewFont::fallback();
+    process_text_fields(&objects(), Path::new("ui.sc"), &out_dir, Some(&font), true).unwrap();
+
+    let preview = out_dir.join("ui/title_1.png").exists();
+    let styles = out_dir.join("ui/text_fields.json").exists();
+    fs::remove_dir_all(&out_dir).unwrap();
+    assert!(preview && styles);
+}